@@ -26,7 +26,7 @@ fn setup_system(n: usize, t: usize) -> (MasterPublicKey, HashMap<usize, MasterSe
             if let Some(share) = shares_map.get(&to) {
                 dkg.receive_shares(to, *from, *share).unwrap();
                 // Estimate communication cost: size of each share and sender ID
-                total_comm_cost += size_of_val(share) + size_of_val(&from);
+                total_comm_cost += size_of_val(share) + size_of_val(from);
             }
         }
     }
@@ -264,6 +264,7 @@ fn benchmark_proof_generation(c: &mut Criterion) {
                         black_box(&proof),
                         black_box(&private_share.verification_key),
                         black_box("proof_context"),
+                        black_box(&private_share.ciphersuite),
                     );
                 });
             },
@@ -295,24 +296,25 @@ fn benchmark_end_to_end(c: &mut Criterion) {
                 &ehr_data,
                 |b, ehr_data| {
                     b.iter(|| {
-                        // Encrypt with AEAD
-                        let aead_key = aead::AEADCipher::generate_key();
-                        let nonce = aead::AEADCipher::generate_nonce();
+                        // Encrypt with AEAD under a key derived from a fresh seed
+                        let seed = aead::AEADCipher::generate_key();
+                        let algorithm = aead::AeadAlgorithm::Aes256Gcm;
+                        let (aead_key, nonce) =
+                            aead::AEADCipher::derive_from_seed(&seed, algorithm).unwrap();
                         let associated_data = b"Medical Record";
 
-                        let encrypted_ehr =
-                            aead::AEADCipher::encrypt(&aead_key, &nonce, ehr_data, associated_data)
-                                .unwrap();
-
-                        // Extract tag from the end of encrypted data
-                        let tag_start = encrypted_ehr.len().saturating_sub(16);
-                        let mut tag = [0u8; 16];
-                        tag.copy_from_slice(&encrypted_ehr[tag_start..]);
+                        let encrypted_ehr = aead::AEADCipher::encrypt_with(
+                            algorithm,
+                            &aead_key,
+                            &nonce,
+                            ehr_data,
+                            associated_data,
+                        )
+                        .unwrap();
 
-                        // Pack and encrypt with DIBTD
-                        let packed_params = aead::AEADCipher::pack_aead_params(&aead_key, &nonce, &tag);
+                        // Encrypt the seed with DIBTD
                         let ciphertext =
-                            encryption::DIBTDEncryption::encrypt(&packed_params, &group_id.id, &mpk)
+                            encryption::DIBTDEncryption::encrypt(&seed, &group_id.id, &mpk)
                                 .unwrap();
 
                         // Generate decryption shares
@@ -329,22 +331,21 @@ fn benchmark_end_to_end(c: &mut Criterion) {
                         }
 
                         // Combine shares to decrypt
-                        let decrypted_params =
+                        let decrypted_seed =
                             encryption::DIBTDEncryption::decrypt(&ciphertext, &decryption_shares, t)
                                 .unwrap();
 
-                        // Unpack and decrypt with AEAD
-                        let (recovered_key, recovered_nonce, recovered_tag) =
-                            aead::AEADCipher::unpack_aead_params(&decrypted_params).unwrap();
-
-                        // For AEAD decryption, we need to reconstruct the full ciphertext with tag
-                        let mut full_ciphertext = encrypted_ehr[..tag_start].to_vec();
-                        full_ciphertext.extend_from_slice(&recovered_tag);
+                        // Re-derive the key and decrypt with AEAD
+                        let mut recovered_seed = [0u8; aead::SEED_LEN];
+                        recovered_seed.copy_from_slice(&decrypted_seed);
+                        let (recovered_key, recovered_nonce) =
+                            aead::AEADCipher::derive_from_seed(&recovered_seed, algorithm).unwrap();
 
-                        aead::AEADCipher::decrypt(
+                        aead::AEADCipher::decrypt_with(
+                            algorithm,
                             &recovered_key,
                             &recovered_nonce,
-                            &full_ciphertext,
+                            &encrypted_ehr,
                             associated_data,
                         )
                         .unwrap();
@@ -1,4 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
+use dibtd_ephr::group::{Curve, Group, Point, Scalar};
 use dibtd_ephr::*;
 use std::collections::HashMap;
 
@@ -129,6 +130,41 @@ fn benchmark_threshold_decryption(c: &mut Criterion) {
     });
 }
 
+fn benchmark_multi_scalar_mul(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Lagrange Combination");
+    
+    // Spans both thresholds in msm.rs: per-term below 8, Straus below 64
+    for t in [2, 4, 8, 16, 32, 64, 128].iter() {
+        // Decryption-share-like points weighted by Lagrange coefficients at 0
+        let indices: Vec<usize> = (1..=*t).collect();
+        let terms: Vec<(Point, Scalar)> = indices
+            .iter()
+            .map(|&i| {
+                let point = Curve::mul_generator(&Curve::scalar_random(&mut rand::thread_rng())).unwrap();
+                (point, utils::lagrange_coefficient(&indices, i, 0).unwrap())
+            })
+            .collect();
+        
+        group.bench_with_input(BenchmarkId::new("Per-share", format!("t={}", t)), &(), |b, _| {
+            b.iter(|| {
+                terms
+                    .iter()
+                    .map(|(point, coeff)| Curve::mul(point, coeff).unwrap())
+                    .reduce(|acc, p| Curve::add(&acc, &p).unwrap())
+                    .unwrap();
+            });
+        });
+        
+        group.bench_with_input(BenchmarkId::new("Multi-scalar", format!("t={}", t)), &(), |b, _| {
+            b.iter(|| {
+                msm::multi_scalar_mul(black_box(&terms)).unwrap();
+            });
+        });
+    }
+    
+    group.finish();
+}
+
 fn benchmark_aead(c: &mut Criterion) {
     let key = aead::AEADCipher::generate_key();
     let nonce = aead::AEADCipher::generate_nonce();
@@ -213,6 +249,7 @@ fn benchmark_proof_generation(c: &mut Criterion) {
                 black_box(&proof),
                 black_box(&private_share.verification_key),
                 black_box("proof_context"),
+                black_box(&private_share.ciphersuite),
             );
         });
     });
@@ -225,6 +262,7 @@ criterion_group!(
     benchmark_encryption,
     benchmark_share_decryption,
     benchmark_threshold_decryption,
+    benchmark_multi_scalar_mul,
     benchmark_aead,
     benchmark_proof_generation
 );
@@ -0,0 +1,64 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use dibtd_ephr::{
+    encryption::DIBTDEncryption, Ciphersuite, Ciphertext, DecryptionShare, PrivateKeyShare,
+};
+use libfuzzer_sys::fuzz_target;
+use secp256k1::{PublicKey, Scalar};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    d: [u8; 33],
+    e: [u8; 33],
+    f: Vec<u8>,
+    delta: [u8; 32],
+    psi: [u8; 32],
+    shares: Vec<(u16, [u8; 33])>,
+    threshold: u8,
+}
+
+// Arbitrary ciphertext/share combinations must be rejected with an error,
+// never a panic.
+fuzz_target!(|input: Input| {
+    let (Ok(d), Ok(e), Ok(delta)) = (
+        PublicKey::from_slice(&input.d),
+        PublicKey::from_slice(&input.e),
+        Scalar::from_be_bytes(input.delta),
+    ) else {
+        return;
+    };
+    let ciphertext = Ciphertext {
+        d,
+        e,
+        f: input.f,
+        delta,
+        ciphersuite: Ciphersuite::default(),
+    };
+
+    if let Ok(psi_i) = Scalar::from_be_bytes(input.psi) {
+        let private_share = PrivateKeyShare {
+            index: 1,
+            psi_i,
+            verification_key: d,
+            ciphersuite: Ciphersuite::default(),
+        };
+        let _ = DIBTDEncryption::share_decrypt(&ciphertext, &private_share);
+    }
+
+    let shares: Vec<DecryptionShare> = input
+        .shares
+        .iter()
+        .filter_map(|(index, lambda)| {
+            PublicKey::from_slice(lambda)
+                .ok()
+                .map(|lambda_i| DecryptionShare {
+                    index: *index as usize,
+                    lambda_i,
+                    proof: None,
+                    ciphersuite: Ciphersuite::default(),
+                })
+        })
+        .collect();
+    let _ = DIBTDEncryption::decrypt(&ciphertext, &shares, input.threshold as usize);
+});
@@ -0,0 +1,24 @@
+#![no_main]
+
+use dibtd_ephr::*;
+use libfuzzer_sys::fuzz_target;
+use serde::de::DeserializeOwned;
+
+fn parse<T: DeserializeOwned>(data: &[u8]) {
+    let _ = serde_json::from_slice::<T>(data);
+}
+
+// Every type that is exchanged with other parties or read back from storage
+fuzz_target!(|data: &[u8]| {
+    parse::<Ciphertext>(data);
+    parse::<DecryptionShare>(data);
+    parse::<Proof>(data);
+    parse::<MasterPublicKey>(data);
+    parse::<GroupIdentity>(data);
+    parse::<EncryptedRecord>(data);
+    parse::<EncryptedField>(data);
+    parse::<FieldPolicy>(data);
+    parse::<SignedConsent>(data);
+    parse::<BreakGlassApproval>(data);
+    parse::<JustificationEntry>(data);
+});
@@ -0,0 +1,31 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use dibtd_ephr::{utils::schnorr_verify, Ciphersuite, Proof};
+use libfuzzer_sys::fuzz_target;
+use secp256k1::{PublicKey, Scalar};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    r: [u8; 33],
+    mu: [u8; 32],
+    public_key: [u8; 33],
+    context: String,
+}
+
+fuzz_target!(|input: Input| {
+    let (Ok(r), Ok(mu), Ok(public_key)) = (
+        PublicKey::from_slice(&input.r),
+        Scalar::from_be_bytes(input.mu),
+        PublicKey::from_slice(&input.public_key),
+    ) else {
+        return;
+    };
+
+    let _ = schnorr_verify(
+        &Proof { r, mu },
+        &public_key,
+        &input.context,
+        &Ciphersuite::default(),
+    );
+});
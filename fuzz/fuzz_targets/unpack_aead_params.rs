@@ -0,0 +1,25 @@
+#![no_main]
+
+use dibtd_ephr::aead::{AEADCipher, AeadParams};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok((key, nonce, tag)) = AEADCipher::unpack_aead_params(data) {
+        let params = AeadParams::unpack(data).unwrap();
+        assert_eq!(
+            (params.key, params.nonce.as_slice(), params.tag),
+            (key, &nonce[..], tag)
+        );
+    }
+
+    if let Ok(params) = AeadParams::unpack(data) {
+        let packed = params.pack().unwrap();
+        // Legacy 60-byte input is re-packed with its algorithm id after the key
+        if data.len() == 60 {
+            assert_eq!(&packed[..32], &data[..32]);
+            assert_eq!(&packed[33..], &data[32..]);
+        } else {
+            assert_eq!(packed, data);
+        }
+    }
+});
@@ -1,15 +1,270 @@
 use aes_gcm::{
-    aead::{Aead, AeadCore, KeyInit, OsRng},
+    aead::{Aead, AeadCore, AeadInPlace, KeyInit, OsRng},
     Aes256Gcm, Key, Nonce,
 };
+use aes_gcm_siv::Aes256GcmSiv;
+use chacha20poly1305::{ChaCha20Poly1305, XChaCha20Poly1305};
 use crate::errors::{DIBTDError, Result};
+use crate::types::{AEADPacket, AEAD_PACKET_VERSION};
+use hkdf::Hkdf;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Data encapsulation mechanism used for record payloads
+///
+/// All four take a 256-bit key and produce a 16-byte tag. ChaCha20-Poly1305
+/// is fast without AES hardware; XChaCha20-Poly1305's 192-bit nonces can be
+/// drawn at random for any practical number of messages per key; AES-GCM-SIV
+/// degrades gracefully if a nonce is ever repeated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AeadAlgorithm {
+    #[default]
+    Aes256Gcm,
+    ChaCha20Poly1305,
+    XChaCha20Poly1305,
+    Aes256GcmSiv,
+}
+
+impl AeadAlgorithm {
+    /// Identifier recorded in packed parameters and stream headers
+    pub fn id(&self) -> u8 {
+        match self {
+            AeadAlgorithm::Aes256Gcm => 1,
+            AeadAlgorithm::ChaCha20Poly1305 => 2,
+            AeadAlgorithm::XChaCha20Poly1305 => 3,
+            AeadAlgorithm::Aes256GcmSiv => 4,
+        }
+    }
+    
+    pub fn from_id(id: u8) -> Result<Self> {
+        match id {
+            1 => Ok(AeadAlgorithm::Aes256Gcm),
+            2 => Ok(AeadAlgorithm::ChaCha20Poly1305),
+            3 => Ok(AeadAlgorithm::XChaCha20Poly1305),
+            4 => Ok(AeadAlgorithm::Aes256GcmSiv),
+            _ => Err(DIBTDError::AEADError(format!("Unknown AEAD algorithm id {}", id))),
+        }
+    }
+    
+    pub fn nonce_len(&self) -> usize {
+        match self {
+            AeadAlgorithm::XChaCha20Poly1305 => 24,
+            _ => 12,
+        }
+    }
+}
+
+/// A keyed instance of one of the supported algorithms
+pub(crate) enum Dem {
+    Aes256Gcm(Box<Aes256Gcm>),
+    ChaCha20Poly1305(ChaCha20Poly1305),
+    XChaCha20Poly1305(XChaCha20Poly1305),
+    Aes256GcmSiv(Box<Aes256GcmSiv>),
+}
+
+impl Dem {
+    pub(crate) fn new(algorithm: AeadAlgorithm, key: &[u8; 32]) -> Self {
+        match algorithm {
+            AeadAlgorithm::Aes256Gcm => {
+                Dem::Aes256Gcm(Box::new(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key))))
+            }
+            AeadAlgorithm::ChaCha20Poly1305 => {
+                Dem::ChaCha20Poly1305(ChaCha20Poly1305::new(key.into()))
+            }
+            AeadAlgorithm::XChaCha20Poly1305 => {
+                Dem::XChaCha20Poly1305(XChaCha20Poly1305::new(key.into()))
+            }
+            AeadAlgorithm::Aes256GcmSiv => {
+                Dem::Aes256GcmSiv(Box::new(Aes256GcmSiv::new(key.into())))
+            }
+        }
+    }
+    
+    fn algorithm(&self) -> AeadAlgorithm {
+        match self {
+            Dem::Aes256Gcm(_) => AeadAlgorithm::Aes256Gcm,
+            Dem::ChaCha20Poly1305(_) => AeadAlgorithm::ChaCha20Poly1305,
+            Dem::XChaCha20Poly1305(_) => AeadAlgorithm::XChaCha20Poly1305,
+            Dem::Aes256GcmSiv(_) => AeadAlgorithm::Aes256GcmSiv,
+        }
+    }
+    
+    fn check_nonce(&self, nonce: &[u8]) -> Result<()> {
+        let expected = self.algorithm().nonce_len();
+        if nonce.len() != expected {
+            return Err(DIBTDError::AEADError(format!(
+                "Nonce must be {} bytes, got {}",
+                expected,
+                nonce.len()
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Encrypt `buffer` in place and append the tag
+    pub(crate) fn encrypt_in_place(
+        &self,
+        nonce: &[u8],
+        associated_data: &[u8],
+        buffer: &mut Vec<u8>,
+    ) -> Result<()> {
+        self.check_nonce(nonce)?;
+        match self {
+            Dem::Aes256Gcm(c) => c.encrypt_in_place(nonce.into(), associated_data, buffer),
+            Dem::ChaCha20Poly1305(c) => c.encrypt_in_place(nonce.into(), associated_data, buffer),
+            Dem::XChaCha20Poly1305(c) => c.encrypt_in_place(nonce.into(), associated_data, buffer),
+            Dem::Aes256GcmSiv(c) => c.encrypt_in_place(nonce.into(), associated_data, buffer),
+        }
+        .map_err(|e| DIBTDError::AEADError(e.to_string()))
+    }
+    
+    /// Check and strip the tag, decrypting `buffer` in place
+    pub(crate) fn decrypt_in_place(
+        &self,
+        nonce: &[u8],
+        associated_data: &[u8],
+        buffer: &mut Vec<u8>,
+    ) -> Result<()> {
+        self.check_nonce(nonce)?;
+        match self {
+            Dem::Aes256Gcm(c) => c.decrypt_in_place(nonce.into(), associated_data, buffer),
+            Dem::ChaCha20Poly1305(c) => c.decrypt_in_place(nonce.into(), associated_data, buffer),
+            Dem::XChaCha20Poly1305(c) => c.decrypt_in_place(nonce.into(), associated_data, buffer),
+            Dem::Aes256GcmSiv(c) => c.decrypt_in_place(nonce.into(), associated_data, buffer),
+        }
+        .map_err(|e| DIBTDError::AEADError(e.to_string()))
+    }
+}
+
+/// Packed parameters of the pre-algorithm-id format: key ‖ nonce ‖ tag, AES-256-GCM
+const LEGACY_PACKED_LEN: usize = 32 + 12 + 16;
+
+/// Set on the algorithm id byte when the payload carries a key commitment
+const KEY_COMMITTING_FLAG: u8 = 0x80;
+
+/// Length of the key commitment prepended by `encrypt_committing`
+pub const COMMITMENT_LEN: usize = 32;
+
+const COMMITMENT_DOMAIN: &[u8] = b"DIBTD-AEAD-key-commitment-v1";
+
+/// Length of the secret DIBTD encapsulates for an envelope
+///
+/// Equal to the H2 output, so every byte of the seed is masked by Θ.
+pub const SEED_LEN: usize = 32;
+
+const SEED_SALT: &[u8] = b"DIBTD-AEAD-seed-v1";
+
+/// Parameters needed to open an AEAD payload, as carried inside a DIBTD ciphertext
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AeadParams {
+    pub algorithm: AeadAlgorithm,
+    /// Whether the payload was produced by `encrypt_committing`
+    pub key_committing: bool,
+    pub key: [u8; 32],
+    pub nonce: Vec<u8>,
+    pub tag: [u8; 16],
+}
+
+impl AeadParams {
+    /// Serialize as key ‖ id ‖ nonce ‖ tag; the id's high bit marks key commitment
+    ///
+    /// The key comes first so that it lies entirely within the 32 bytes DIBTD
+    /// masks with H2.
+    pub fn pack(&self) -> Result<Vec<u8>> {
+        if self.nonce.len() != self.algorithm.nonce_len() {
+            return Err(DIBTDError::AEADError("Invalid nonce length".to_string()));
+        }
+        
+        let mut id = self.algorithm.id();
+        if self.key_committing {
+            id |= KEY_COMMITTING_FLAG;
+        }
+        
+        let mut packed = Vec::with_capacity(32 + 1 + self.nonce.len() + 16);
+        packed.extend_from_slice(&self.key);
+        packed.push(id);
+        packed.extend_from_slice(&self.nonce);
+        packed.extend_from_slice(&self.tag);
+        Ok(packed)
+    }
+    
+    /// Parse the output of `pack`
+    ///
+    /// Also accepts the 60-byte AES-256-GCM format written before the
+    /// algorithm id was added, so existing records still open.
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        if data.len() < 32 + 1 {
+            return Err(DIBTDError::AEADError("Invalid packed data length".to_string()));
+        }
+        
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&data[..32]);
+        let (algorithm, key_committing, rest) = if data.len() == LEGACY_PACKED_LEN {
+            (AeadAlgorithm::Aes256Gcm, false, &data[32..])
+        } else {
+            let id = data[32];
+            (
+                AeadAlgorithm::from_id(id & !KEY_COMMITTING_FLAG)?,
+                id & KEY_COMMITTING_FLAG != 0,
+                &data[33..],
+            )
+        };
+        
+        let nonce_len = algorithm.nonce_len();
+        if rest.len() != nonce_len + 16 {
+            return Err(DIBTDError::AEADError("Invalid packed data length".to_string()));
+        }
+        
+        let mut tag = [0u8; 16];
+        let nonce = rest[..nonce_len].to_vec();
+        tag.copy_from_slice(&rest[nonce_len..]);
+        
+        Ok(Self {
+            algorithm,
+            key_committing,
+            key,
+            nonce,
+            tag,
+        })
+    }
+    
+    /// Open a payload sealed under these parameters
+    pub fn decrypt(&self, payload: &[u8], associated_data: &[u8]) -> Result<Vec<u8>> {
+        if self.key_committing {
+            AEADCipher::decrypt_committing(self.algorithm, &self.key, &self.nonce, payload, associated_data)
+        } else {
+            AEADCipher::decrypt_with(self.algorithm, &self.key, &self.nonce, payload, associated_data)
+        }
+    }
+}
+
+/// Commitment to the key and nonce: SHA-256(domain ‖ algorithm id ‖ key ‖ nonce)
+pub(crate) fn key_commitment(algorithm: AeadAlgorithm, key: &[u8; 32], nonce: &[u8]) -> [u8; COMMITMENT_LEN] {
+    let mut hasher = Sha256::new();
+    hasher.update(COMMITMENT_DOMAIN);
+    hasher.update([algorithm.id()]);
+    hasher.update(key);
+    hasher.update(nonce);
+    hasher.finalize().into()
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
 
 pub struct AEADCipher;
 
 impl AEADCipher {
     /// Generate a new AEAD key
     pub fn generate_key() -> [u8; 32] {
-        let key = Aes256Gcm::generate_key(OsRng);
+        Self::generate_key_with_rng(&mut OsRng)
+    }
+    
+    /// Generate a new AEAD key from a caller-supplied RNG
+    pub fn generate_key_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> [u8; 32] {
+        let key = Aes256Gcm::generate_key(rng);
         let mut key_bytes = [0u8; 32];
         key_bytes.copy_from_slice(&key);
         key_bytes
@@ -17,13 +272,55 @@ impl AEADCipher {
     
     /// Generate a new nonce
     pub fn generate_nonce() -> [u8; 12] {
-        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        Self::generate_nonce_with_rng(&mut OsRng)
+    }
+    
+    /// Generate a new nonce from a caller-supplied RNG
+    pub fn generate_nonce_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> [u8; 12] {
+        let nonce = Aes256Gcm::generate_nonce(rng);
         let mut nonce_bytes = [0u8; 12];
         nonce_bytes.copy_from_slice(&nonce);
         nonce_bytes
     }
     
-    /// Encrypt data with AEAD
+    /// Generate a random nonce of the right length for `algorithm`
+    pub fn generate_nonce_for(algorithm: AeadAlgorithm) -> Vec<u8> {
+        Self::generate_nonce_for_with_rng(algorithm, &mut OsRng)
+    }
+    
+    /// Generate a nonce for `algorithm` from a caller-supplied RNG
+    pub fn generate_nonce_for_with_rng<R: RngCore + CryptoRng>(
+        algorithm: AeadAlgorithm,
+        rng: &mut R,
+    ) -> Vec<u8> {
+        let mut nonce = vec![0u8; algorithm.nonce_len()];
+        rng.fill_bytes(&mut nonce);
+        nonce
+    }
+    
+    /// Derive the key and nonce for `algorithm` from an encapsulated seed
+    ///
+    /// HKDF-SHA256 with the algorithm id as info, so a seed never yields the
+    /// same key for two algorithms. Each envelope draws a fresh seed, which
+    /// keeps the derived nonce unique.
+    pub fn derive_from_seed(
+        seed: &[u8; SEED_LEN],
+        algorithm: AeadAlgorithm,
+    ) -> Result<([u8; 32], Vec<u8>)> {
+        let mut okm = vec![0u8; 32 + algorithm.nonce_len()];
+        Hkdf::<Sha256>::new(Some(SEED_SALT), seed)
+            .expand(&[algorithm.id()], &mut okm)
+            .map_err(|e| DIBTDError::AEADError(e.to_string()))?;
+        
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&okm[..32]);
+        Ok((key, okm.split_off(32)))
+    }
+    
+    /// Encrypt data with AES-256-GCM
+    ///
+    /// Always AES-256-GCM with a 12-byte nonce; use `encrypt_with` to choose
+    /// the algorithm.
     pub fn encrypt(
         key: &[u8; 32],
         nonce: &[u8; 12],
@@ -41,7 +338,10 @@ impl AEADCipher {
             .map_err(|e| DIBTDError::AEADError(e.to_string()))
     }
     
-    /// Decrypt data with AEAD
+    /// Decrypt data with AES-256-GCM
+    ///
+    /// The counterpart of `encrypt`; use `decrypt_with` for the other
+    /// algorithms.
     pub fn decrypt(
         key: &[u8; 32],
         nonce: &[u8; 12],
@@ -59,29 +359,148 @@ impl AEADCipher {
             .map_err(|e| DIBTDError::AEADError(e.to_string()))
     }
     
-    /// Pack key, nonce, and tag into a single message for DIBTD encryption
-    pub fn pack_aead_params(key: &[u8; 32], nonce: &[u8; 12], tag: &[u8; 16]) -> Vec<u8> {
-        let mut packed = Vec::with_capacity(32 + 12 + 16);
-        packed.extend_from_slice(key);
-        packed.extend_from_slice(nonce);
-        packed.extend_from_slice(tag);
-        packed
+    /// Encrypt data with the chosen algorithm; the tag is appended
+    pub fn encrypt_with(
+        algorithm: AeadAlgorithm,
+        key: &[u8; 32],
+        nonce: &[u8],
+        plaintext: &[u8],
+        associated_data: &[u8],
+    ) -> Result<Vec<u8>> {
+        let mut buffer = plaintext.to_vec();
+        Dem::new(algorithm, key).encrypt_in_place(nonce, associated_data, &mut buffer)?;
+        Ok(buffer)
     }
     
-    /// Unpack AEAD parameters from decrypted message
-    pub fn unpack_aead_params(data: &[u8]) -> Result<([u8; 32], [u8; 12], [u8; 16])> {
-        if data.len() != 60 {
-            return Err(DIBTDError::AEADError("Invalid packed data length".to_string()));
+    /// Decrypt data with the chosen algorithm
+    pub fn decrypt_with(
+        algorithm: AeadAlgorithm,
+        key: &[u8; 32],
+        nonce: &[u8],
+        ciphertext: &[u8],
+        associated_data: &[u8],
+    ) -> Result<Vec<u8>> {
+        let mut buffer = ciphertext.to_vec();
+        Dem::new(algorithm, key).decrypt_in_place(nonce, associated_data, &mut buffer)?;
+        Ok(buffer)
+    }
+    
+    /// Encrypt with a key commitment prepended: commitment ‖ ciphertext ‖ tag
+    ///
+    /// Plain AES-GCM and ChaCha20-Poly1305 are not key-committing: a
+    /// malicious encryptor can build one ciphertext that opens under several
+    /// keys. Checking the commitment first means a payload opens under at
+    /// most one key, so every recipient of a multi-group envelope sees the
+    /// same plaintext.
+    pub fn encrypt_committing(
+        algorithm: AeadAlgorithm,
+        key: &[u8; 32],
+        nonce: &[u8],
+        plaintext: &[u8],
+        associated_data: &[u8],
+    ) -> Result<Vec<u8>> {
+        let mut payload = key_commitment(algorithm, key, nonce).to_vec();
+        payload.extend_from_slice(&Self::encrypt_with(algorithm, key, nonce, plaintext, associated_data)?);
+        Ok(payload)
+    }
+    
+    /// Check the key commitment, then decrypt
+    pub fn decrypt_committing(
+        algorithm: AeadAlgorithm,
+        key: &[u8; 32],
+        nonce: &[u8],
+        payload: &[u8],
+        associated_data: &[u8],
+    ) -> Result<Vec<u8>> {
+        if payload.len() < COMMITMENT_LEN
+            || !constant_time_eq(&payload[..COMMITMENT_LEN], &key_commitment(algorithm, key, nonce))
+        {
+            return Err(DIBTDError::AEADError("Key commitment mismatch".to_string()));
         }
+        Self::decrypt_with(algorithm, key, nonce, &payload[COMMITMENT_LEN..], associated_data)
+    }
+    
+    /// Encrypt into a key-committing `AEADPacket`
+    pub fn encrypt_packet(
+        algorithm: AeadAlgorithm,
+        key: &[u8; 32],
+        nonce: &[u8],
+        plaintext: &[u8],
+        associated_data: &[u8],
+    ) -> Result<AEADPacket> {
+        let mut payload = Self::encrypt_committing(algorithm, key, nonce, plaintext, associated_data)?;
         
-        let mut key = [0u8; 32];
-        let mut nonce = [0u8; 12];
+        let mut commitment = [0u8; COMMITMENT_LEN];
         let mut tag = [0u8; 16];
+        commitment.copy_from_slice(&payload[..COMMITMENT_LEN]);
+        tag.copy_from_slice(&payload[payload.len() - 16..]);
+        payload.truncate(payload.len() - 16);
         
-        key.copy_from_slice(&data[0..32]);
-        nonce.copy_from_slice(&data[32..44]);
-        tag.copy_from_slice(&data[44..60]);
+        Ok(AEADPacket {
+            version: AEAD_PACKET_VERSION,
+            algorithm,
+            nonce: nonce.to_vec(),
+            commitment,
+            ciphertext: payload.split_off(COMMITMENT_LEN),
+            tag,
+            associated_data: associated_data.to_vec(),
+        })
+    }
+    
+    /// Check the packet's version and key commitment, then decrypt it
+    pub fn decrypt_packet(key: &[u8; 32], packet: &AEADPacket) -> Result<Vec<u8>> {
+        if packet.version != AEAD_PACKET_VERSION {
+            return Err(DIBTDError::AEADError(format!(
+                "Unsupported packet version {}",
+                packet.version
+            )));
+        }
+        
+        let mut payload = Vec::with_capacity(COMMITMENT_LEN + packet.ciphertext.len() + 16);
+        payload.extend_from_slice(&packet.commitment);
+        payload.extend_from_slice(&packet.ciphertext);
+        payload.extend_from_slice(&packet.tag);
+        Self::decrypt_committing(
+            packet.algorithm,
+            key,
+            &packet.nonce,
+            &payload,
+            &packet.associated_data,
+        )
+    }
+    
+    /// Pack AES-256-GCM key, nonce, and tag into a single message for DIBTD encryption
+    ///
+    /// The parameters describe a payload from `encrypt`; use `AeadParams::pack`
+    /// for the other algorithms and for key-committing payloads.
+    pub fn pack_aead_params(key: &[u8; 32], nonce: &[u8; 12], tag: &[u8; 16]) -> Vec<u8> {
+        AeadParams {
+            algorithm: AeadAlgorithm::Aes256Gcm,
+            key_committing: false,
+            key: *key,
+            nonce: nonce.to_vec(),
+            tag: *tag,
+        }
+        .pack()
+        .expect("12-byte nonces are valid for AES-256-GCM")
+    }
+    
+    /// Unpack AES-256-GCM parameters from decrypted message
+    ///
+    /// Accepts what `pack_aead_params` produces as well as the 60-byte format
+    /// written before the algorithm id was added. Parameters of any other
+    /// algorithm, or of a key-committing payload, are rejected; use
+    /// `AeadParams::unpack` for those.
+    pub fn unpack_aead_params(data: &[u8]) -> Result<([u8; 32], [u8; 12], [u8; 16])> {
+        let params = AeadParams::unpack(data)?;
+        if params.algorithm != AeadAlgorithm::Aes256Gcm || params.key_committing {
+            return Err(DIBTDError::AEADError(
+                "Packed parameters are not for plain AES-256-GCM".to_string(),
+            ));
+        }
         
-        Ok((key, nonce, tag))
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&params.nonce);
+        Ok((params.key, nonce, params.tag))
     }
-}
\ No newline at end of file
+}
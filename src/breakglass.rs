@@ -0,0 +1,134 @@
+use crate::{
+    encryption::DIBTDEncryption,
+    errors::{DIBTDError, Result, Stage},
+    group::Point,
+    types::*,
+    utils::{schnorr_prove, schnorr_verify, to_hex},
+};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub struct BreakGlassAccess;
+
+impl BreakGlassAccess {
+    /// Signing context binding an approval to a record and a justification
+    pub fn approval_context(record_id: &str, justification: &Justification) -> Result<String> {
+        let encoded = serde_json::to_vec(justification)
+            .map_err(|e| DIBTDError::SerializationError(e.to_string()))?;
+        let digest = Sha256::digest(&encoded);
+        Ok(format!("break-glass:{}:{}", record_id, to_hex(&digest)))
+    }
+
+    /// Approve a break-glass request as an on-call member of the emergency group
+    ///
+    /// The approval carries the member's decryption share of the emergency key
+    /// and a Schnorr signature over the justification under the member's key share.
+    pub fn approve(
+        record: &EncryptedRecord,
+        justification: &Justification,
+        private_share: &PrivateKeyShare,
+    ) -> Result<BreakGlassApproval> {
+        let (_, emergency_key) = Self::emergency_envelope(record)?;
+        let share = DIBTDEncryption::share_decrypt_labeled(
+            emergency_key,
+            &record.associated_data()?,
+            private_share,
+        )?;
+
+        let context = Self::approval_context(&record.record_id, justification)?;
+        let signature = schnorr_prove(&private_share.psi_i, &context, &private_share.ciphersuite)?;
+
+        Ok(BreakGlassApproval { share, signature })
+    }
+
+    /// Check a quorum of approvals and produce the signed justification entry
+    ///
+    /// `emergency_group` is the caller's registered emergency group; the
+    /// record's header must name the same group, and its threshold, not the
+    /// one stored in the header, sets the quorum. Every approval must come
+    /// from a distinct emergency group member and carry a valid signature
+    /// over the justification.
+    pub fn verify_approvals(
+        record: &EncryptedRecord,
+        emergency_group: &GroupIdentity,
+        justification: &Justification,
+        approvals: &[BreakGlassApproval],
+        verification_keys: &HashMap<usize, Point>,
+    ) -> Result<JustificationEntry> {
+        let (sealed_for, emergency_key) = Self::emergency_envelope(record)?;
+        if sealed_for != emergency_group {
+            return Err(DIBTDError::BreakGlassDenied(format!(
+                "Record {} is not sealed for emergency group {}",
+                record.record_id, emergency_group.id
+            )));
+        }
+
+        if justification.reason.trim().is_empty() || justification.requested_by.trim().is_empty() {
+            return Err(DIBTDError::BreakGlassDenied(
+                "Justification must name the requester and a reason".to_string(),
+            ));
+        }
+        if approvals.len() < emergency_group.threshold {
+            return Err(DIBTDError::InsufficientShares(
+                approvals.len(),
+                emergency_group.threshold,
+            ));
+        }
+
+        let context = Self::approval_context(&record.record_id, justification)?;
+        let mut seen = HashSet::new();
+        let mut signatures = Vec::with_capacity(approvals.len());
+
+        for approval in approvals {
+            let index = approval.share.index;
+            if !seen.insert(index) {
+                return Err(DIBTDError::DuplicateIndex(index));
+            }
+
+            let verification_key = verification_keys.get(&index).ok_or_else(|| {
+                DIBTDError::BreakGlassDenied(format!("Unknown emergency member {}", index))
+            })?;
+            if !schnorr_verify(
+                &approval.signature,
+                verification_key,
+                &context,
+                &emergency_key.ciphersuite,
+            )? {
+                return Err(DIBTDError::InvalidShare {
+                    index,
+                    stage: Stage::ShareVerification,
+                });
+            }
+
+            signatures.push((index, approval.signature.clone()));
+        }
+
+        Ok(JustificationEntry {
+            record_id: record.record_id.clone(),
+            patient_pseudonym: record.metadata.patient_pseudonym.clone(),
+            emergency_group: emergency_group.id.clone(),
+            justification: justification.clone(),
+            signatures,
+            opened_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        })
+    }
+
+    /// The emergency group and its encapsulated key for a break-glass record
+    pub fn emergency_envelope(record: &EncryptedRecord) -> Result<(&GroupIdentity, &Ciphertext)> {
+        match (
+            record.header.mode,
+            &record.header.emergency_group,
+            &record.emergency_key,
+        ) {
+            (EnvelopeMode::BreakGlass, Some(group), Some(key)) => Ok((group, key)),
+            _ => Err(DIBTDError::BreakGlassDenied(format!(
+                "Record {} has no break-glass envelope",
+                record.record_id
+            ))),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Ciphersuite descriptors
+//!
+//! A `Ciphersuite` names the curve, the hash and the AEAD algorithm a system
+//! runs with, e.g. `secp256k1-SHA256-AESGCM`. It is fixed at DKG time in
+//! `SystemParams` and recorded in every key share and ciphertext, so data
+//! from one suite is refused by keys of another instead of failing somewhere
+//! deep in decryption, and a deployment can move to a new suite by running a
+//! new DKG while old ciphertexts stay identifiable.
+
+use crate::aead::AeadAlgorithm;
+use crate::errors::{DIBTDError, Result};
+use crate::group::{Curve, Group};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Prime-order groups a ciphersuite can name
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CurveId {
+    Secp256k1,
+    P256,
+    Ristretto255,
+}
+
+impl CurveId {
+    /// Short curve name, used both in the suite string and in the
+    /// domain-separation tags
+    pub const fn name(&self) -> &'static str {
+        match self {
+            CurveId::Secp256k1 => "secp256k1",
+            CurveId::P256 => "p256",
+            CurveId::Ristretto255 => "ristretto255",
+        }
+    }
+}
+
+/// Hash functions a ciphersuite can name
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HashId {
+    Sha256,
+}
+
+impl HashId {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            HashId::Sha256 => "SHA256",
+        }
+    }
+}
+
+fn aead_name(algorithm: AeadAlgorithm) -> &'static str {
+    match algorithm {
+        AeadAlgorithm::Aes256Gcm => "AESGCM",
+        AeadAlgorithm::ChaCha20Poly1305 => "CHACHA20POLY1305",
+        AeadAlgorithm::XChaCha20Poly1305 => "XCHACHA20POLY1305",
+        AeadAlgorithm::Aes256GcmSiv => "AESGCMSIV",
+    }
+}
+
+/// Curve, hash and AEAD algorithm of a DIBTD deployment
+///
+/// Serialized as its string form, e.g. `"secp256k1-SHA256-AESGCM"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Ciphersuite {
+    pub curve: CurveId,
+    pub hash: HashId,
+    pub aead: AeadAlgorithm,
+}
+
+impl Ciphersuite {
+    /// The suite of this build's curve with SHA-256 and the given AEAD algorithm
+    pub fn with_aead(aead: AeadAlgorithm) -> Self {
+        Self {
+            curve: Curve::ID,
+            hash: HashId::Sha256,
+            aead,
+        }
+    }
+
+    /// Whether this build can run the suite; only its compiled curve can
+    pub fn is_supported(&self) -> bool {
+        self.curve == Curve::ID
+    }
+
+    /// Fail with `UnsupportedCiphersuite` unless this build can run the suite
+    pub fn ensure_supported(&self) -> Result<()> {
+        if !self.is_supported() {
+            return Err(DIBTDError::UnsupportedCiphersuite(self.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Fail with `CiphersuiteMismatch` unless both suites name the same
+    /// curve, hash and AEAD algorithm
+    pub fn ensure_matches(&self, found: &Ciphersuite) -> Result<()> {
+        if self != found {
+            return Err(DIBTDError::CiphersuiteMismatch {
+                expected: self.to_string(),
+                found: found.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for Ciphersuite {
+    /// This build's curve with SHA-256 and AES-256-GCM
+    fn default() -> Self {
+        Self::with_aead(AeadAlgorithm::default())
+    }
+}
+
+impl fmt::Display for Ciphersuite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}-{}", self.curve.name(), self.hash.name(), aead_name(self.aead))
+    }
+}
+
+impl FromStr for Ciphersuite {
+    type Err = DIBTDError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let unknown = || DIBTDError::UnsupportedCiphersuite(s.to_string());
+        let mut parts = s.split('-');
+        let (Some(curve), Some(hash), Some(aead), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(unknown());
+        };
+
+        let curve = [CurveId::Secp256k1, CurveId::P256, CurveId::Ristretto255]
+            .into_iter()
+            .find(|id| id.name() == curve)
+            .ok_or_else(unknown)?;
+        let hash = [HashId::Sha256]
+            .into_iter()
+            .find(|id| id.name() == hash)
+            .ok_or_else(unknown)?;
+        let aead = [
+            AeadAlgorithm::Aes256Gcm,
+            AeadAlgorithm::ChaCha20Poly1305,
+            AeadAlgorithm::XChaCha20Poly1305,
+            AeadAlgorithm::Aes256GcmSiv,
+        ]
+        .into_iter()
+        .find(|algorithm| aead_name(*algorithm) == aead)
+        .ok_or_else(unknown)?;
+
+        Ok(Self { curve, hash, aead })
+    }
+}
+
+impl From<Ciphersuite> for String {
+    fn from(suite: Ciphersuite) -> Self {
+        suite.to_string()
+    }
+}
+
+impl TryFrom<String> for Ciphersuite {
+    type Error = DIBTDError;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
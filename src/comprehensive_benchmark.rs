@@ -14,6 +14,7 @@ struct BenchmarkResults {
     share_decrypt_times: Vec<(usize, f64, f64, f64)>, // (n_users, zk_verify_time, dibtd_decrypt_time, total_time)
     aes_decrypt_times: Vec<(usize, f64)>, // (data_size_kb, time_seconds)
     end_to_end_times: Vec<(usize, f64)>, // (data_size_kb, total_time_seconds)
+    batch_times: Vec<(usize, &'static str, f64, f64)>, // (records, operation, serial_time, batch_time)
 }
 
 impl BenchmarkResults {
@@ -26,6 +27,7 @@ impl BenchmarkResults {
             share_decrypt_times: Vec::new(),
             aes_decrypt_times: Vec::new(),
             end_to_end_times: Vec::new(),
+            batch_times: Vec::new(),
         }
     }
 }
@@ -52,6 +54,7 @@ fn main() {
     benchmark_share_operations(&mut results);
     benchmark_aes_operations(&mut results);
     benchmark_end_to_end(&mut results);
+    benchmark_batch_operations(&mut results);
     
     println!();
     println!("{}", "-".repeat(80));
@@ -357,20 +360,16 @@ fn benchmark_end_to_end(results: &mut BenchmarkResults) {
                 let start = Instant::now();
                 
                 // Full end-to-end process
-                let aead_key = aead::AEADCipher::generate_key();
-                let nonce = aead::AEADCipher::generate_nonce();
+                let seed = aead::AEADCipher::generate_key();
+                let algorithm = aead::AeadAlgorithm::Aes256Gcm;
                 let associated_data = b"Medical Record";
                 
-                // AEAD encrypt
-                if let Ok(encrypted_ehr) = aead::AEADCipher::encrypt(&aead_key, &nonce, &data, associated_data) {
-                    // Pack parameters
-                    let tag_start = encrypted_ehr.len().saturating_sub(16);
-                    let mut tag = [0u8; 16];
-                    tag.copy_from_slice(&encrypted_ehr[tag_start..]);
-                    let packed_params = aead::AEADCipher::pack_aead_params(&aead_key, &nonce, &tag);
-                    
-                    // DIBTD encrypt
-                    if let Ok(ciphertext) = encryption::DIBTDEncryption::encrypt(&packed_params, &group_id.id, &mpk) {
+                // AEAD encrypt under a key derived from the seed
+                let encrypted_ehr = aead::AEADCipher::derive_from_seed(&seed, algorithm)
+                    .and_then(|(aead_key, nonce)| aead::AEADCipher::encrypt_with(algorithm, &aead_key, &nonce, &data, associated_data));
+                if let Ok(encrypted_ehr) = encrypted_ehr {
+                    // DIBTD encrypt the seed
+                    if let Ok(ciphertext) = encryption::DIBTDEncryption::encrypt(&seed, &group_id.id, &mpk) {
                         // Generate shares
                         let mut decryption_shares = Vec::new();
                         for i in 1..=group_id.threshold {
@@ -382,10 +381,12 @@ fn benchmark_end_to_end(results: &mut BenchmarkResults) {
                         }
                         
                         // DIBTD decrypt
-                        if let Ok(decrypted_params) = encryption::DIBTDEncryption::decrypt(&ciphertext, &decryption_shares, group_id.threshold) {
-                            // Unpack and AEAD decrypt
-                            if let Ok((recovered_key, recovered_nonce, _)) = aead::AEADCipher::unpack_aead_params(&decrypted_params) {
-                                let _ = aead::AEADCipher::decrypt(&recovered_key, &recovered_nonce, &encrypted_ehr, associated_data);
+                        if let Ok(decrypted_seed) = encryption::DIBTDEncryption::decrypt(&ciphertext, &decryption_shares, group_id.threshold) {
+                            // Re-derive the key and AEAD decrypt
+                            let mut recovered_seed = [0u8; aead::SEED_LEN];
+                            recovered_seed.copy_from_slice(&decrypted_seed);
+                            if let Ok((recovered_key, recovered_nonce)) = aead::AEADCipher::derive_from_seed(&recovered_seed, algorithm) {
+                                let _ = aead::AEADCipher::decrypt_with(algorithm, &recovered_key, &recovered_nonce, &encrypted_ehr, associated_data);
                             }
                         }
                     }
@@ -399,6 +400,97 @@ fn benchmark_end_to_end(results: &mut BenchmarkResults) {
     }
 }
 
+/// Benchmark serial calls against the batch APIs (parallel with the `parallel` feature)
+fn benchmark_batch_operations(results: &mut BenchmarkResults) {
+    println!("\n8. Benchmarking Batch Operations...");
+    
+    let record_counts = vec![16, 64, 256, 1024];
+    
+    if let Ok((mpk, master_shares)) = setup_dkg_system(5, 3) {
+        let group_id = GroupIdentity {
+            id: "test_group".to_string(),
+            threshold: 2,
+            members: 4,
+        };
+        
+        if let Ok(private_shares) = dkg::distributed_keygen(&master_shares, &group_id, 2) {
+            let private_share = &private_shares[&1];
+            
+            for n_records in record_counts {
+                println!("  Testing {} records...", n_records);
+                
+                let records: Vec<Vec<u8>> = (0..n_records).map(|i| (i as u32).to_be_bytes().repeat(8)).collect();
+                let messages: Vec<&[u8]> = records.iter().map(|r| r.as_slice()).collect();
+                
+                // Encryption
+                let start = Instant::now();
+                for message in &messages {
+                    let _ = encryption::DIBTDEncryption::encrypt(message, &group_id.id, &mpk);
+                }
+                let serial_time = start.elapsed().as_secs_f64();
+                
+                let start = Instant::now();
+                let batch_result = encryption::DIBTDEncryption::encrypt_many(&messages, &group_id.id, &mpk);
+                let batch_time = start.elapsed().as_secs_f64();
+                
+                let ciphertexts = match batch_result {
+                    Ok(ciphertexts) => ciphertexts,
+                    Err(e) => {
+                        println!("    Batch encryption failed: {}", e);
+                        continue;
+                    }
+                };
+                results.batch_times.push((n_records, "encrypt", serial_time, batch_time));
+                println!("    Encrypt      serial: {:.6}s, batch: {:.6}s", serial_time, batch_time);
+                
+                // Share generation
+                let start = Instant::now();
+                for ciphertext in &ciphertexts {
+                    let _ = encryption::DIBTDEncryption::share_decrypt(ciphertext, private_share);
+                }
+                let serial_time = start.elapsed().as_secs_f64();
+                
+                let start = Instant::now();
+                let batch_result = encryption::DIBTDEncryption::share_decrypt_many(&ciphertexts, private_share);
+                let batch_time = start.elapsed().as_secs_f64();
+                
+                let shares = match batch_result {
+                    Ok(shares) => shares,
+                    Err(e) => {
+                        println!("    Batch share decryption failed: {}", e);
+                        continue;
+                    }
+                };
+                results.batch_times.push((n_records, "share_decrypt", serial_time, batch_time));
+                println!("    Share        serial: {:.6}s, batch: {:.6}s", serial_time, batch_time);
+                
+                // Proof verification
+                let proofs: Vec<Proof> = shares
+                    .iter()
+                    .filter_map(|share| crypto::ZKProof::prove_share(private_share, share, "test").ok())
+                    .collect();
+                let items: Vec<_> = proofs
+                    .iter()
+                    .map(|proof| (proof, &private_share.verification_key))
+                    .collect();
+                
+                let start = Instant::now();
+                for (proof, key) in &items {
+                    let _ = crypto::ZKProof::verify_share(proof, key, "test");
+                }
+                let serial_time = start.elapsed().as_secs_f64();
+                
+                let start = Instant::now();
+                let _ = crypto::ZKProof::verify_shares_many(&items, "test");
+                let batch_time = start.elapsed().as_secs_f64();
+                
+                results.batch_times.push((n_records, "verify_share", serial_time, batch_time));
+                println!("    Verify proof serial: {:.6}s, batch: {:.6}s", serial_time, batch_time);
+            }
+        }
+    }
+}
+
 /// Setup DKG system for testing
 fn setup_dkg_system(n: usize, t: usize) -> Result<(MasterPublicKey, HashMap<usize, MasterSecretShare>)> {
     let mut dkg = dkg::DKGProtocol::new(n, t)?;
@@ -502,6 +594,20 @@ fn generate_comprehensive_report(results: &BenchmarkResults) -> String {
     }
     output.push_str("\n");
     
+    // Table 6: Batch Throughput
+    output.push_str("Table 6: Batch Throughput (records per second)\n");
+    output.push_str(&"=".repeat(50));
+    output.push_str("\n");
+    output.push_str(&format!("{:<8} | {:<14} | {:<12} | {:<12}\n", "Records", "Operation", "Serial", "Batch"));
+    output.push_str(&"-".repeat(55));
+    output.push_str("\n");
+    
+    for (records, operation, serial, batch) in &results.batch_times {
+        output.push_str(&format!("{:<8} | {:<14} | {:<12.1} | {:<12.1}\n",
+                                 records, operation, *records as f64 / serial, *records as f64 / batch));
+    }
+    output.push_str("\n");
+    
     // Performance Summary
     output.push_str("PERFORMANCE SUMMARY\n");
     output.push_str(&"=".repeat(50));
@@ -0,0 +1,161 @@
+use crate::{
+    encryption::DIBTDEncryption,
+    errors::{DIBTDError, Result},
+    group::{Curve, Group},
+    types::*,
+    utils::to_hex,
+};
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+pub struct PatientConsent;
+
+impl PatientConsent {
+    /// Sign a consent grant with the patient's secp256k1 key
+    pub fn sign(grant: ConsentGrant, patient_key: &SecretKey) -> Result<SignedConsent> {
+        let secp = Secp256k1::new();
+        let message = Self::grant_digest(&grant)?;
+        let signature = secp.sign_ecdsa(&message, patient_key);
+
+        Ok(SignedConsent { grant, signature })
+    }
+
+    /// Verify that a consent grant was signed by the given patient key
+    pub fn verify(consent: &SignedConsent, patient_key: &PublicKey) -> Result<()> {
+        let secp = Secp256k1::new();
+        let message = Self::grant_digest(&consent.grant)?;
+
+        secp.verify_ecdsa(&message, &consent.signature, patient_key)
+            .map_err(|_| DIBTDError::ConsentDenied("Invalid consent signature".to_string()))
+    }
+
+    /// Pin a record to its current encapsulated key for a `Records` scope
+    pub fn consented_record(record: &EncryptedRecord) -> ConsentedRecord {
+        ConsentedRecord {
+            record_id: record.record_id.clone(),
+            key_digest: Self::key_digest(&record.encapsulated_key),
+        }
+    }
+
+    /// SHA-256 over D ‖ E ‖ δ ‖ F; D, E and δ have a fixed length
+    fn key_digest(ciphertext: &Ciphertext) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(b"DIBTD-CONSENT-KEY:");
+        hasher.update(Curve::point_to_bytes(&ciphertext.d));
+        hasher.update(Curve::point_to_bytes(&ciphertext.e));
+        hasher.update(Curve::scalar_to_bytes(&ciphertext.delta));
+        hasher.update(&ciphertext.f);
+        to_hex(&hasher.finalize())
+    }
+
+    fn grant_digest(grant: &ConsentGrant) -> Result<Message> {
+        let encoded =
+            serde_json::to_vec(grant).map_err(|e| DIBTDError::SerializationError(e.to_string()))?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"DIBTD-CONSENT:");
+        hasher.update(&encoded);
+        Ok(Message::from_digest(hasher.finalize().into()))
+    }
+}
+
+/// Policy-checking wrapper around decryption share generation
+///
+/// A group member only releases a decryption share for a record when the
+/// patient has signed a consent grant covering that record, for the member's
+/// group and purpose of use, that has not expired. Grants for specific
+/// records also pin each record's encapsulated key, so a key moved into a
+/// consented record from elsewhere gets no share.
+#[derive(Default)]
+pub struct ConsentGuard {
+    patient_keys: HashMap<String, PublicKey>,
+}
+
+impl ConsentGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the consent verification key of a patient pseudonym
+    pub fn register_patient(&mut self, patient_pseudonym: &str, patient_key: PublicKey) {
+        self.patient_keys
+            .insert(patient_pseudonym.to_string(), patient_key);
+    }
+
+    /// Check a consent grant against a record, purpose and time (Unix seconds)
+    pub fn check(
+        &self,
+        record: &EncryptedRecord,
+        consent: &SignedConsent,
+        purpose: &str,
+        now: u64,
+    ) -> Result<()> {
+        let grant = &consent.grant;
+        let patient = &record.metadata.patient_pseudonym;
+
+        let patient_key = self.patient_keys.get(patient).ok_or_else(|| {
+            DIBTDError::ConsentDenied(format!("No consent key registered for {}", patient))
+        })?;
+        PatientConsent::verify(consent, patient_key)?;
+
+        if &grant.patient_pseudonym != patient {
+            return Err(DIBTDError::ConsentDenied(
+                "Consent was granted by a different patient".to_string(),
+            ));
+        }
+        if grant.group_id != record.metadata.group.id {
+            return Err(DIBTDError::ConsentDenied(format!(
+                "Consent does not cover group {}",
+                record.metadata.group.id
+            )));
+        }
+        if let ConsentScope::Records(records) = &grant.scope {
+            let covered = records
+                .iter()
+                .find(|covered| covered.record_id == record.record_id)
+                .ok_or_else(|| {
+                    DIBTDError::ConsentDenied(format!(
+                        "Consent does not cover record {}",
+                        record.record_id
+                    ))
+                })?;
+            if covered.key_digest != PatientConsent::key_digest(&record.encapsulated_key) {
+                return Err(DIBTDError::ConsentDenied(format!(
+                    "Consent does not cover the encapsulated key of record {}",
+                    record.record_id
+                )));
+            }
+        }
+        if grant.purpose != purpose {
+            return Err(DIBTDError::ConsentDenied(format!(
+                "Consent does not cover purpose {}",
+                purpose
+            )));
+        }
+        if now < grant.issued_at || now >= grant.expires_at {
+            return Err(DIBTDError::ConsentDenied(
+                "Consent is not valid at this time".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Generate a decryption share for a record only under a valid consent
+    pub fn share_decrypt(
+        &self,
+        record: &EncryptedRecord,
+        private_share: &PrivateKeyShare,
+        consent: &SignedConsent,
+        purpose: &str,
+        now: u64,
+    ) -> Result<DecryptionShare> {
+        self.check(record, consent, purpose, now)?;
+        DIBTDEncryption::share_decrypt_labeled(
+            &record.encapsulated_key,
+            &record.associated_data()?,
+            private_share,
+        )
+    }
+}
@@ -1,9 +1,17 @@
 use crate::{
+    ciphersuite::Ciphersuite,
     errors::Result,
+    group::{Curve, Group, Point},
+    hash::{hash_to_scalar, Domain},
+    msm::multi_scalar_mul_with_base,
     types::*,
-    utils::{schnorr_prove, schnorr_verify},
+    utils::{
+        map_ordered, scalar_add, scalar_mul, scalar_negate, schnorr_challenge, schnorr_prove,
+        schnorr_prove_with_rng, schnorr_verify, share_proof_challenge,
+    },
 };
-use secp256k1::{PublicKey, SecretKey, Secp256k1};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use std::ops::Range;
 
 pub struct ZKProof;
 
@@ -14,32 +22,247 @@ impl ZKProof {
         _decryption_share: &DecryptionShare,
         context: &str,
     ) -> Result<Proof> {
-        schnorr_prove(&private_share.psi_i, context)
+        schnorr_prove(&private_share.psi_i, context, &private_share.ciphersuite)
+    }
+    
+    /// Generate a zero-knowledge proof for a decryption share using a caller-supplied RNG
+    pub fn prove_share_with_rng<R: RngCore + CryptoRng>(
+        private_share: &PrivateKeyShare,
+        _decryption_share: &DecryptionShare,
+        context: &str,
+        rng: &mut R,
+    ) -> Result<Proof> {
+        schnorr_prove_with_rng(&private_share.psi_i, context, &private_share.ciphersuite, rng)
     }
     
     /// Verify a zero-knowledge proof for a decryption share
+    ///
+    /// `suite` is the one the prover's key share belongs to. Malformed proofs
+    /// are reported as not verifying.
     pub fn verify_share(
         proof: &Proof,
-        verification_key: &PublicKey,
+        verification_key: &Point,
         context: &str,
+        suite: &Ciphersuite,
     ) -> bool {
-        schnorr_verify(proof, verification_key, context)
+        matches!(schnorr_verify(proof, verification_key, context, suite), Ok(true))
+    }
+    
+    /// Verify many proofs individually, one result per `(proof, key)` pair
+    ///
+    /// Unlike `batch_verify` this reports which proofs fail; it runs across
+    /// all cores with the `parallel` feature and keeps the input order.
+    pub fn verify_shares_many(
+        items: &[(&Proof, &Point)],
+        context: &str,
+        suite: &Ciphersuite,
+    ) -> Vec<bool> {
+        map_ordered(items, |&(proof, verification_key)| {
+            Self::verify_share(proof, verification_key, context, suite)
+        })
     }
     
     /// Batch verification of multiple proofs
     pub fn batch_verify(
         proofs: &[Proof],
-        verification_keys: &[PublicKey],
+        verification_keys: &[Point],
+        context: &str,
+        suite: &Ciphersuite,
+    ) -> bool {
+        Self::batch_verify_with_rng(proofs, verification_keys, context, suite, &mut OsRng)
+    }
+    
+    /// Batch verification of multiple proofs using a caller-supplied RNG
+    ///
+    /// Checks a random linear combination of the individual equations with a
+    /// single multi-scalar multiplication:
+    /// Σ ρ_i * R_i + Σ ρ_i c_i * Y_i - (Σ ρ_i μ_i) * G = O.
+    /// Accepts a batch containing an invalid proof only with negligible
+    /// probability; use `find_invalid` to locate it.
+    pub fn batch_verify_with_rng<R: RngCore + CryptoRng>(
+        proofs: &[Proof],
+        verification_keys: &[Point],
         context: &str,
+        suite: &Ciphersuite,
+        rng: &mut R,
     ) -> bool {
         if proofs.len() != verification_keys.len() {
             return false;
         }
+        if proofs.len() == 1 {
+            return Self::verify_share(&proofs[0], &verification_keys[0], context, suite);
+        }
+        
+        let mut mu_sum = Curve::SCALAR_ZERO;
+        let mut terms = Vec::with_capacity(2 * proofs.len());
+        
+        for (i, (proof, vk)) in proofs.iter().zip(verification_keys).enumerate() {
+            // A zero response never verifies individually
+            if proof.mu == Curve::SCALAR_ZERO {
+                return false;
+            }
+            let c = schnorr_challenge(&proof.r, vk, context, suite);
+            
+            // The first weight can be fixed to one without loss of soundness
+            let rho = if i == 0 { Curve::SCALAR_ONE } else { Curve::scalar_random(&mut *rng) };
+            mu_sum = scalar_add(&mu_sum, &scalar_mul(&rho, &proof.mu));
+            terms.push((proof.r, rho));
+            terms.push((*vk, scalar_mul(&rho, &c)));
+        }
+        
+        matches!(multi_scalar_mul_with_base(&scalar_negate(&mu_sum), &terms), Ok(None))
+    }
+    
+    /// Indices of the proofs that do not verify
+    ///
+    /// Bisects the batch, so a handful of bad proofs among many good ones
+    /// costs a logarithmic number of batch verifications each.
+    pub fn find_invalid(
+        proofs: &[Proof],
+        verification_keys: &[Point],
+        context: &str,
+        suite: &Ciphersuite,
+    ) -> Vec<usize> {
+        if proofs.len() != verification_keys.len() {
+            return (0..proofs.len().max(verification_keys.len())).collect();
+        }
+        
+        bisect(proofs.len(), &|range: Range<usize>| {
+            Self::batch_verify(&proofs[range.clone()], &verification_keys[range], context, suite)
+        })
+    }
+    
+    /// Prove that a decryption share Λ_i = ψ_i * D uses the member's key share
+    ///
+    /// The nonce is derived from ψ_i and the statement, so share generation
+    /// stays deterministic and needs no RNG.
+    pub fn prove_decryption_share(
+        private_share: &PrivateKeyShare,
+        d: &Point,
+        lambda_i: &Point,
+    ) -> Result<ShareProof> {
+        let mut nonce_input = Curve::scalar_to_bytes(&private_share.psi_i).to_vec();
+        nonce_input.extend_from_slice(&Curve::point_to_bytes(d));
+        nonce_input.extend_from_slice(&Curve::point_to_bytes(lambda_i));
+        let suite = &private_share.ciphersuite;
+        let k = hash_to_scalar(&nonce_input, &Domain::ShareNonce.dst(suite));
+        
+        let a_1 = Curve::mul_generator(&k)?;
+        let a_2 = Curve::mul(d, &k)?;
+        let c = share_proof_challenge(
+            &private_share.verification_key,
+            d,
+            lambda_i,
+            &a_1,
+            &a_2,
+            suite,
+        );
+        let response = scalar_add(&k, &scalar_mul(&c, &private_share.psi_i));
+        
+        Ok(ShareProof { a_1, a_2, response })
+    }
+    
+    /// Verify the proof carried by a decryption share of `ciphertext`
+    ///
+    /// A share without a proof does not verify.
+    pub fn verify_decryption_share(
+        share: &DecryptionShare,
+        verification_key: &Point,
+        ciphertext: &Ciphertext,
+    ) -> bool {
+        Self::batch_verify_decryption_shares(ciphertext, &[(share, verification_key)])
+    }
+    
+    /// Batch verification of decryption share proofs for one ciphertext
+    pub fn batch_verify_decryption_shares(
+        ciphertext: &Ciphertext,
+        items: &[(&DecryptionShare, &Point)],
+    ) -> bool {
+        Self::batch_verify_decryption_shares_with_rng(ciphertext, items, &mut OsRng)
+    }
+    
+    /// Batch verification of decryption share proofs using a caller-supplied RNG
+    ///
+    /// Both equations of every proof, z * P = A_1 + c * VK and
+    /// z * D = A_2 + c * Λ, go into one random linear combination checked
+    /// with a single multi-scalar multiplication. Fails if any share has no
+    /// proof or names another suite than the ciphertext; use
+    /// `find_invalid_decryption_shares` to locate bad ones.
+    pub fn batch_verify_decryption_shares_with_rng<R: RngCore + CryptoRng>(
+        ciphertext: &Ciphertext,
+        items: &[(&DecryptionShare, &Point)],
+        rng: &mut R,
+    ) -> bool {
+        let (d, suite) = (&ciphertext.d, &ciphertext.ciphersuite);
+        let mut base_sum = Curve::SCALAR_ZERO;
+        let mut d_sum = Curve::SCALAR_ZERO;
+        let mut terms = Vec::with_capacity(4 * items.len() + 1);
+        
+        for (i, &(share, vk)) in items.iter().enumerate() {
+            let Some(proof) = &share.proof else {
+                return false;
+            };
+            if share.ciphersuite != *suite {
+                return false;
+            }
+            let c = share_proof_challenge(vk, d, &share.lambda_i, &proof.a_1, &proof.a_2, suite);
+            
+            // The first weight can be fixed to one without loss of soundness
+            let rho = if i == 0 { Curve::SCALAR_ONE } else { Curve::scalar_random(&mut *rng) };
+            let sigma = Curve::scalar_random(&mut *rng);
+            base_sum = scalar_add(&base_sum, &scalar_mul(&rho, &proof.response));
+            d_sum = scalar_add(&d_sum, &scalar_mul(&sigma, &proof.response));
+            terms.push((proof.a_1, rho));
+            terms.push((*vk, scalar_mul(&rho, &c)));
+            terms.push((proof.a_2, sigma));
+            terms.push((share.lambda_i, scalar_mul(&sigma, &c)));
+        }
+        if items.is_empty() {
+            return true;
+        }
         
-        proofs.iter()
-            .zip(verification_keys.iter())
-            .all(|(proof, vk)| Self::verify_share(proof, vk, context))
+        // Σ ρ A_1 + Σ ρ c VK + Σ σ A_2 + Σ σ c Λ - (Σ σ z) D - (Σ ρ z) P = O
+        terms.push((*d, scalar_negate(&d_sum)));
+        matches!(multi_scalar_mul_with_base(&scalar_negate(&base_sum), &terms), Ok(None))
     }
+    
+    /// Indices of the decryption shares whose proofs do not verify
+    ///
+    /// Checks the whole batch first and bisects only if it fails.
+    pub fn find_invalid_decryption_shares(
+        ciphertext: &Ciphertext,
+        items: &[(&DecryptionShare, &Point)],
+    ) -> Vec<usize> {
+        bisect(items.len(), &|range: Range<usize>| {
+            Self::batch_verify_decryption_shares(ciphertext, &items[range])
+        })
+    }
+}
+
+/// Indices in `0..len` that fail `batch_ok` on their own, found by halving
+/// every failing range
+fn bisect(len: usize, batch_ok: &dyn Fn(Range<usize>) -> bool) -> Vec<usize> {
+    let mut invalid = Vec::new();
+    bisect_range(0..len, batch_ok, &mut invalid);
+    invalid
+}
+
+fn bisect_range(
+    range: Range<usize>,
+    batch_ok: &dyn Fn(Range<usize>) -> bool,
+    invalid: &mut Vec<usize>,
+) {
+    if range.is_empty() || batch_ok(range.clone()) {
+        return;
+    }
+    if range.len() == 1 {
+        invalid.push(range.start);
+        return;
+    }
+    
+    let mid = range.start + range.len() / 2;
+    bisect_range(range.start..mid, batch_ok, invalid);
+    bisect_range(mid..range.end, batch_ok, invalid);
 }
 
 pub struct KeyDerivation;
@@ -49,11 +272,10 @@ impl KeyDerivation {
     pub fn derive_group_public_key(
         mpk: &MasterPublicKey,
         group_id: &str,
-    ) -> Result<PublicKey> {
-        let secp = Secp256k1::new();
-        let id_hash = crate::utils::hash_h1(group_id.as_bytes());
-        let gamma_scaled = mpk.gamma.mul_tweak(&secp, &id_hash)?;
-        Ok(mpk.y.combine(&gamma_scaled)?)
+    ) -> Result<Point> {
+        let id_hash = crate::utils::hash_h1(group_id.as_bytes(), &mpk.params.ciphersuite);
+        let gamma_scaled = Curve::mul(&mpk.gamma, &id_hash)?;
+        Curve::add(&mpk.y, &gamma_scaled)
     }
     
     /// Verify that a private key share is valid for a group
@@ -62,9 +284,7 @@ impl KeyDerivation {
         _mpk: &MasterPublicKey,
         _group_id: &str,
     ) -> Result<bool> {
-        let secp = Secp256k1::new();
-        let sk = SecretKey::from_slice(&share.psi_i.to_be_bytes())?;
-        let expected_vk = PublicKey::from_secret_key(&secp, &sk);
+        let expected_vk = Curve::mul_generator(&share.psi_i)?;
         Ok(expected_vk == share.verification_key)
     }
 }
\ No newline at end of file
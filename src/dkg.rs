@@ -1,42 +1,69 @@
 use crate::{
-    errors::{DIBTDError, Result},
+    ciphersuite::Ciphersuite,
+    errors::{DIBTDError, Result, Stage},
+    group::{Curve, Group, Point, Scalar},
+    msm::multi_scalar_mul,
     types::*,
-    utils::{lagrange_coefficient, scalar_add, scalar_mul},
+    utils::{
+        lagrange_coefficient, scalar_add, scalar_from_u32, scalar_inverse, scalar_mul,
+        scalar_negate,
+    },
 };
-use secp256k1::{PublicKey, Scalar, SecretKey, Secp256k1};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
 use std::collections::HashMap;
 
 pub struct DKGProtocol {
     pub participants: HashMap<usize, DKGParticipant>,
     pub n: usize,
     pub t: usize,
+    pub ciphersuite: Ciphersuite,
 }
 
 impl DKGProtocol {
     pub fn new(n: usize, t: usize) -> Result<Self> {
+        Self::with_ciphersuite(n, t, Ciphersuite::default())
+    }
+    
+    /// Set up a DKG for a system running the given ciphersuite
+    ///
+    /// Fails with `UnsupportedCiphersuite` if the suite's curve is not the one
+    /// this build was compiled for.
+    pub fn with_ciphersuite(n: usize, t: usize, ciphersuite: Ciphersuite) -> Result<Self> {
         if t > n || t == 0 {
             return Err(DIBTDError::InvalidThreshold(t, n));
         }
+        ciphersuite.ensure_supported()?;
         
         Ok(Self {
             participants: HashMap::new(),
             n,
             t,
+            ciphersuite,
         })
     }
     
     /// Initialize a participant in the DKG protocol
     pub fn init_participant(&mut self, index: usize) -> Result<()> {
+        self.init_participant_with_rng(index, &mut OsRng)
+    }
+    
+    /// Initialize a participant with polynomials drawn from a caller-supplied RNG
+    pub fn init_participant_with_rng<R: RngCore + CryptoRng>(
+        &mut self,
+        index: usize,
+        rng: &mut R,
+    ) -> Result<()> {
         if index == 0 || index > self.n {
             return Err(DIBTDError::DKGProtocolFailed(
                 "Invalid participant index".to_string()
             ));
         }
         
-        let f_0 = Polynomial::new(self.t - 1);
-        let f_1 = Polynomial::new(self.t - 1);
+        let degree = self.t.checked_sub(1)
+            .ok_or(DIBTDError::InvalidThreshold(self.t, self.n))?;
+        let f_0 = Polynomial::new_with_rng(degree, rng);
+        let f_1 = Polynomial::new_with_rng(degree, rng);
         
-        let secp = Secp256k1::new();
         let mut commitments_0 = Vec::new();
         let mut commitments_1 = Vec::new();
         
@@ -44,12 +71,8 @@ impl DKGProtocol {
             let share_0 = f_0.evaluate(i);
             let share_1 = f_1.evaluate(i);
             
-            // Convert scalars to secret keys for public key generation
-            let sk_0 = SecretKey::from_slice(&share_0.to_be_bytes())?;
-            let sk_1 = SecretKey::from_slice(&share_1.to_be_bytes())?;
-            
-            let comm_0 = PublicKey::from_secret_key(&secp, &sk_0);
-            let comm_1 = PublicKey::from_secret_key(&secp, &sk_1);
+            let comm_0 = Curve::mul_generator(&share_0)?;
+            let comm_1 = Curve::mul_generator(&share_1)?;
             
             commitments_0.push(comm_0);
             commitments_1.push(comm_1);
@@ -93,6 +116,12 @@ impl DKGProtocol {
         from: usize,
         shares: (Scalar, Scalar)
     ) -> Result<()> {
+        if from == 0 || from > self.n || from == to {
+            return Err(DIBTDError::DKGProtocolFailed(
+                format!("Invalid sender index {}", from)
+            ));
+        }
+        
         let participant = self.participants.get_mut(&to)
             .ok_or_else(|| DIBTDError::DKGProtocolFailed("Participant not found".to_string()))?;
         
@@ -100,40 +129,149 @@ impl DKGProtocol {
         Ok(())
     }
     
-    /// Verify received shares
-    pub fn verify_shares(&self, participant_index: usize) -> Result<bool> {
+    /// Verify received shares against their senders' commitments
+    ///
+    /// Fails with `InvalidShare` naming the first sender, in index order,
+    /// whose share does not match its commitments.
+    pub fn verify_shares(&self, participant_index: usize) -> Result<()> {
         let participant = self.participants.get(&participant_index)
             .ok_or_else(|| DIBTDError::DKGProtocolFailed("Participant not found".to_string()))?;
         
-        let secp = Secp256k1::new();
+        let position = participant_index.checked_sub(1)
+            .ok_or_else(|| DIBTDError::DKGProtocolFailed("Invalid participant index".to_string()))?;
         
-        for (&from, &(share_0, share_1)) in &participant.shares_received {
+        let mut senders: Vec<usize> = participant.shares_received.keys().copied().collect();
+        senders.sort_unstable();
+        for from in senders {
+            let (share_0, share_1) = participant.shares_received[&from];
             let sender = self.participants.get(&from)
                 .ok_or_else(|| DIBTDError::DKGProtocolFailed("Sender not found".to_string()))?;
+            let invalid = DIBTDError::InvalidShare { index: from, stage: Stage::KeyGeneration };
             
-            // Convert scalars to secret keys for public key generation
-            let sk_0 = SecretKey::from_slice(&share_0.to_be_bytes())?;
-            let sk_1 = SecretKey::from_slice(&share_1.to_be_bytes())?;
-            
-            let expected_0 = PublicKey::from_secret_key(&secp, &sk_0);
-            let expected_1 = PublicKey::from_secret_key(&secp, &sk_1);
+            let expected_0 = Curve::mul_generator(&share_0)?;
+            let expected_1 = Curve::mul_generator(&share_1)?;
             
-            if sender.commitments_0[participant_index - 1] != expected_0 ||
-               sender.commitments_1[participant_index - 1] != expected_1 {
-                return Ok(false);
+            let (Some(commitment_0), Some(commitment_1)) = (
+                sender.commitments_0.get(position),
+                sender.commitments_1.get(position),
+            ) else {
+                return Err(invalid);
+            };
+            if *commitment_0 != expected_0 || *commitment_1 != expected_1 {
+                return Err(invalid);
             }
         }
         
-        Ok(true)
+        Ok(())
+    }
+    
+    /// Check that a participant's commitments come from polynomials of degree t - 1
+    ///
+    /// The commitments are f(j) * P for j = 1..n, i.e. a Reed-Solomon
+    /// codeword "in the exponent". A random vector v of the dual code
+    /// satisfies Σ v_j * f(j) = 0 for every polynomial of degree below t and
+    /// misses any other with negligible probability, so both commitment
+    /// vectors are checked with a single multi-scalar multiplication.
+    pub fn verify_commitments(&self, from: usize) -> Result<bool> {
+        self.verify_commitments_with_rng(from, &mut OsRng)
+    }
+    
+    /// Check a participant's commitments using a caller-supplied RNG
+    pub fn verify_commitments_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        from: usize,
+        rng: &mut R,
+    ) -> Result<bool> {
+        let dual = self.dual_codeword(rng)?;
+        self.check_commitments(from, &dual, rng)
+    }
+    
+    /// Random codeword of the dual of the degree t - 1 evaluation code at 1..n
+    ///
+    /// v_j = g(j) / Π_{m≠j} (j - m) for a random polynomial g of degree
+    /// n - t - 1; the denominators are ±(j - 1)!(n - j)!.
+    fn dual_codeword<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<Vec<Scalar>> {
+        if self.t == 0 || self.t > self.n {
+            return Err(DIBTDError::DKGProtocolFailed(
+                format!("Threshold {} is invalid for {} participants", self.t, self.n)
+            ));
+        }
+        if self.t == self.n {
+            // Every vector of n values lies on a polynomial of degree n - 1
+            return Ok(vec![Curve::SCALAR_ZERO; self.n]);
+        }
+        
+        let mut factorials = vec![Curve::SCALAR_ONE; self.n];
+        for k in 1..self.n {
+            factorials[k] = scalar_mul(&factorials[k - 1], &scalar_from_u32(k as u32));
+        }
+        let mut inverse_factorials = vec![Curve::SCALAR_ONE; self.n];
+        inverse_factorials[self.n - 1] = scalar_inverse(&factorials[self.n - 1])
+            .ok_or(DIBTDError::InvalidThreshold(self.t, self.n))?;
+        for k in (1..self.n).rev() {
+            inverse_factorials[k - 1] = scalar_mul(&inverse_factorials[k], &scalar_from_u32(k as u32));
+        }
+        
+        let g = Polynomial::new_with_rng(self.n - self.t - 1, rng);
+        Ok((1..=self.n)
+            .map(|j| {
+                let weight = scalar_mul(&inverse_factorials[j - 1], &inverse_factorials[self.n - j]);
+                let weight = if (self.n - j) % 2 == 1 { scalar_negate(&weight) } else { weight };
+                scalar_mul(&g.evaluate(j), &weight)
+            })
+            .collect())
+    }
+    
+    fn check_commitments<R: RngCore + CryptoRng>(
+        &self,
+        from: usize,
+        dual: &[Scalar],
+        rng: &mut R,
+    ) -> Result<bool> {
+        let participant = self.participants.get(&from)
+            .ok_or_else(|| DIBTDError::DKGProtocolFailed("Participant not found".to_string()))?;
+        
+        if participant.commitments_0.len() != self.n || participant.commitments_1.len() != self.n {
+            return Ok(false);
+        }
+        
+        // Σ v_j * C0_j + ρ Σ v_j * C1_j = O checks both vectors at once
+        let rho = Curve::scalar_random(rng);
+        let mut terms = Vec::with_capacity(2 * self.n);
+        for (j, v) in dual.iter().enumerate() {
+            terms.push((participant.commitments_0[j], *v));
+            terms.push((participant.commitments_1[j], scalar_mul(&rho, v)));
+        }
+        
+        Ok(multi_scalar_mul(&terms)?.is_none())
     }
     
     /// Compute the final master keys after all shares are received and verified
+    ///
+    /// Fails with `InvalidShare` naming the first participant, in index order,
+    /// whose commitments are not consistent with a polynomial of degree t - 1.
     pub fn finalize(&self) -> Result<(MasterPublicKey, HashMap<usize, MasterSecretShare>)> {
+        self.finalize_with_rng(&mut OsRng)
+    }
+    
+    /// Compute the final master keys, checking commitments with a caller-supplied RNG
+    pub fn finalize_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(MasterPublicKey, HashMap<usize, MasterSecretShare>)> {
         if self.participants.len() < self.t {
             return Err(DIBTDError::InsufficientShares(self.participants.len(), self.t));
         }
         
-        let secp = Secp256k1::new();
+        let dual = self.dual_codeword(rng)?;
+        let mut indices: Vec<usize> = self.participants.keys().copied().collect();
+        indices.sort_unstable();
+        for index in indices {
+            if !self.check_commitments(index, &dual, rng)? {
+                return Err(DIBTDError::InvalidShare { index, stage: Stage::KeyGeneration });
+            }
+        }
+        
         let mut secret_shares = HashMap::new();
         
         // Compute aggregated shares for each participant
@@ -153,13 +291,14 @@ impl DKGProtocol {
                 index,
                 s_i,
                 z_i,
+                ciphersuite: self.ciphersuite,
             });
         }
         
         // Compute master public key using Lagrange interpolation
         let indices: Vec<usize> = (1..=self.t).collect();
-        let mut y: Option<PublicKey> = None;
-        let mut gamma: Option<PublicKey> = None;
+        let mut y: Option<Point> = None;
+        let mut gamma: Option<Point> = None;
         
         for &i in &indices {
             let share = secret_shares.get(&i)
@@ -170,29 +309,28 @@ impl DKGProtocol {
             let s_i_scaled = scalar_mul(&share.s_i, &coeff);
             let z_i_scaled = scalar_mul(&share.z_i, &coeff);
             
-            let sk_y = SecretKey::from_slice(&s_i_scaled.to_be_bytes())?;
-            let sk_gamma = SecretKey::from_slice(&z_i_scaled.to_be_bytes())?;
-            
-            let y_i = PublicKey::from_secret_key(&secp, &sk_y);
-            let gamma_i = PublicKey::from_secret_key(&secp, &sk_gamma);
+            let y_i = Curve::mul_generator(&s_i_scaled)?;
+            let gamma_i = Curve::mul_generator(&z_i_scaled)?;
             
             y = Some(match y {
                 None => y_i,
-                Some(acc) => acc.combine(&y_i)?,
+                Some(acc) => Curve::add(&acc, &y_i)?,
             });
             
             gamma = Some(match gamma {
                 None => gamma_i,
-                Some(acc) => acc.combine(&gamma_i)?,
+                Some(acc) => Curve::add(&acc, &gamma_i)?,
             });
         }
         
+        let no_shares = || DIBTDError::DKGProtocolFailed("No shares to aggregate".to_string());
         let mpk = MasterPublicKey {
-            y: y.unwrap(),
-            gamma: gamma.unwrap(),
+            y: y.ok_or_else(no_shares)?,
+            gamma: gamma.ok_or_else(no_shares)?,
             params: SystemParams {
                 n: self.n,
                 t: self.t,
+                ciphersuite: self.ciphersuite,
             },
         };
         
@@ -206,11 +344,43 @@ pub fn distributed_keygen(
     group_id: &GroupIdentity,
     threshold: usize,
 ) -> Result<HashMap<usize, PrivateKeyShare>> {
+    distributed_keygen_with_rng(master_shares, group_id, threshold, &mut OsRng)
+}
+
+/// Generate distributed private keys for a group using a caller-supplied RNG
+pub fn distributed_keygen_with_rng<R: RngCore + CryptoRng>(
+    master_shares: &HashMap<usize, MasterSecretShare>,
+    group_id: &GroupIdentity,
+    threshold: usize,
+    rng: &mut R,
+) -> Result<HashMap<usize, PrivateKeyShare>> {
+    if threshold == 0 {
+        return Err(DIBTDError::InvalidThreshold(threshold, master_shares.len()));
+    }
+    if master_shares.len() < threshold {
+        return Err(DIBTDError::InsufficientShares(master_shares.len(), threshold));
+    }
+    if group_id.threshold == 0 || group_id.threshold > group_id.members {
+        return Err(DIBTDError::InvalidThreshold(group_id.threshold, group_id.members));
+    }
+    
     let mut private_shares = HashMap::new();
-    let secp = Secp256k1::new();
+    
+    // The contributing DKGC nodes must all run the same suite
+    let mut ciphersuite = None;
+    for share in master_shares.values().take(threshold) {
+        let suite = *ciphersuite.get_or_insert(share.ciphersuite);
+        if share.ciphersuite != suite {
+            return Err(DIBTDError::CiphersuiteMismatch {
+                expected: suite.to_string(),
+                found: share.ciphersuite.to_string(),
+            });
+        }
+    }
+    let ciphersuite = ciphersuite.unwrap_or_default();
     
     // Compute H1(group_id)
-    let id_hash = crate::utils::hash_h1(group_id.id.as_bytes());
+    let id_hash = crate::utils::hash_h1(group_id.id.as_bytes(), &ciphersuite);
     
     // Each DKGC node i has shares s_i and z_i
     // For the group, they compute ψ_i = s_i + H1(id) * z_i
@@ -230,7 +400,7 @@ pub fn distributed_keygen(
     
     // First, reconstruct the group secret using Lagrange interpolation
     let indices: Vec<usize> = group_shares.iter().map(|(i, _)| *i).collect();
-    let mut group_secret = Scalar::ZERO;
+    let mut group_secret = Curve::SCALAR_ZERO;
     
     for (index, share) in &group_shares {
         let coeff = crate::utils::lagrange_coefficient(&indices, *index, 0)?;
@@ -239,19 +409,19 @@ pub fn distributed_keygen(
     }
     
     // Create a new polynomial with the group secret as constant term
-    let poly = Polynomial::with_constant(group_id.threshold - 1, group_secret);
+    let poly = Polynomial::with_constant_with_rng(group_id.threshold - 1, group_secret, rng);
     
     // Generate shares for each group member
     for member_index in 1..=group_id.members {
         let psi_i = poly.evaluate(member_index);
         
-        let sk = SecretKey::from_slice(&psi_i.to_be_bytes())?;
-        let verification_key = PublicKey::from_secret_key(&secp, &sk);
+        let verification_key = Curve::mul_generator(&psi_i)?;
         
         private_shares.insert(member_index, PrivateKeyShare {
             index: member_index,
             psi_i,
             verification_key,
+            ciphersuite,
         });
     }
     
@@ -1,11 +1,16 @@
 use crate::{
-    errors::{DIBTDError, Result},
+    ciphersuite::Ciphersuite,
+    crypto::ZKProof,
+    errors::{DIBTDError, Result, Stage},
+    group::{Curve, Group, Point},
+    msm::multi_scalar_mul_with_base,
+    threshold::Quorum,
     types::*,
-    utils::{hash_h1, hash_h2, hash_h2_bytes, hash_h3, xor_bytes, pad_or_truncate, 
-            scalar_add, scalar_mul},
+    utils::{hash_h1, hash_h2_bytes, hash_h3, xor_bytes, pad_or_truncate, 
+            map_ordered, scalar_add, scalar_mul, scalar_negate},
 };
-use secp256k1::{PublicKey, Scalar, SecretKey, Secp256k1};
-use rand::rngs::OsRng;
+use rand::{rngs::{OsRng, StdRng}, CryptoRng, RngCore, SeedableRng};
+use std::collections::HashMap;
 
 pub struct DIBTDEncryption;
 
@@ -16,46 +21,82 @@ impl DIBTDEncryption {
         group_id: &str,
         mpk: &MasterPublicKey,
     ) -> Result<Ciphertext> {
-        let secp = Secp256k1::new();
+        Self::encrypt_with_rng(message, group_id, mpk, &mut OsRng)
+    }
+    
+    /// Encrypt a message for a group identity using a caller-supplied RNG
+    pub fn encrypt_with_rng<R: RngCore + CryptoRng>(
+        message: &[u8],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+        rng: &mut R,
+    ) -> Result<Ciphertext> {
+        Self::encrypt_labeled_with_rng(message, b"", group_id, mpk, rng)
+    }
+    
+    /// Encrypt a message for a group identity under a public label
+    ///
+    /// The label (e.g. a record id) is bound into the well-formedness proof
+    /// and the key derivation, so the ciphertext only decrypts under the
+    /// same label. The unlabeled APIs use the empty label.
+    pub fn encrypt_labeled(
+        message: &[u8],
+        label: &[u8],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+    ) -> Result<Ciphertext> {
+        Self::encrypt_labeled_with_rng(message, label, group_id, mpk, &mut OsRng)
+    }
+    
+    /// Encrypt a message under a public label using a caller-supplied RNG
+    pub fn encrypt_labeled_with_rng<R: RngCore + CryptoRng>(
+        message: &[u8],
+        label: &[u8],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+        rng: &mut R,
+    ) -> Result<Ciphertext> {
+        // The suite is hashed into Θ and the well-formedness proof
+        let suite = mpk.params.ciphersuite;
+        suite.ensure_supported()?;
+        let bound = transcript_label(&suite, label);
         
         // Select random u
-        let u = Scalar::random_custom(&mut OsRng);
+        let u = Curve::scalar_random(rng);
         
         // Compute Δ = (Y + H1(id) * Γ) * u
-        let id_hash = hash_h1(group_id.as_bytes());
-        let gamma_scaled = mpk.gamma.mul_tweak(&secp, &id_hash)?;
-        let combined = mpk.y.combine(&gamma_scaled)?;
-        let delta = combined.mul_tweak(&secp, &u)?;
+        let id_hash = hash_h1(group_id.as_bytes(), &suite);
+        let gamma_scaled = Curve::mul(&mpk.gamma, &id_hash)?;
+        let combined = Curve::add(&mpk.y, &gamma_scaled)?;
+        let delta = Curve::mul(&combined, &u)?;
         
         // Compute D = u * P
-        let u_key = SecretKey::from_slice(&u.to_be_bytes())?;
-        let d = PublicKey::from_secret_key(&secp, &u_key);
+        let d = Curve::mul_generator(&u)?;
         
         // Compute r = H1(m, Δ) and E = r * P
         let mut r_input = Vec::new();
         r_input.extend_from_slice(message);
-        r_input.extend_from_slice(&delta.serialize());
-        let r = hash_h1(&r_input);
-        let r_key = SecretKey::from_slice(&r.to_be_bytes())?;
-        let e = PublicKey::from_secret_key(&secp, &r_key);
+        r_input.extend_from_slice(&Curve::point_to_bytes(&delta));
+        let r = hash_h1(&r_input, &suite);
+        let e = Curve::mul_generator(&r)?;
         
-        // Compute Θ = H2(Δ)
-        let theta = hash_h2(&delta);
+        // Compute Θ = H2(Δ|L)
+        let theta = derive_theta(&delta, &bound, &suite);
         let theta_padded = pad_or_truncate(&theta, message.len());
         
         // Compute Ω = H2(E|Θ)
         let mut omega_input = Vec::new();
-        omega_input.extend_from_slice(&e.serialize());
+        omega_input.extend_from_slice(&Curve::point_to_bytes(&e));
         omega_input.extend_from_slice(&theta);
-        let omega = hash_h2_bytes(&omega_input);
+        let omega = hash_h2_bytes(&omega_input, &suite);
         let omega_padded = pad_or_truncate(&omega, message.len());
         
         // Compute X = Θ ⊕ m and F = Ω ⊕ X
         let x = xor_bytes(&theta_padded, message);
         let f = xor_bytes(&omega_padded, &x);
         
-        // Calculate δ = u + r * H3(D, E, F)
-        let h3_val = hash_h3(&d, &e, &f);
+        // Calculate δ = u + r * H3(D, E, F, L)
+        let h3_val = hash_h3(&d, &e, &f, &bound, &suite);
         let r_h3 = scalar_mul(&r, &h3_val);
         let delta_scalar = scalar_add(&u, &r_h3);
         
@@ -64,73 +105,188 @@ impl DIBTDEncryption {
             e,
             f,
             delta: delta_scalar,
+            ciphersuite: suite,
         })
     }
     
+    /// Encrypt many messages for the same group
+    ///
+    /// Runs across all cores with the `parallel` feature; ciphertexts are
+    /// returned in the order of `messages`.
+    pub fn encrypt_many(
+        messages: &[&[u8]],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+    ) -> Result<Vec<Ciphertext>> {
+        Self::encrypt_many_with_rng(messages, group_id, mpk, &mut OsRng)
+    }
+    
+    /// Encrypt many messages using a caller-supplied RNG
+    pub fn encrypt_many_with_rng<R: RngCore + CryptoRng>(
+        messages: &[&[u8]],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+        rng: &mut R,
+    ) -> Result<Vec<Ciphertext>> {
+        let items: Vec<(&[u8], &[u8])> = messages.iter().map(|&m| (m, &b""[..])).collect();
+        Self::encrypt_many_labeled_with_rng(&items, group_id, mpk, rng)
+    }
+    
+    /// Encrypt many `(message, label)` pairs for the same group
+    pub fn encrypt_many_labeled(
+        items: &[(&[u8], &[u8])],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+    ) -> Result<Vec<Ciphertext>> {
+        Self::encrypt_many_labeled_with_rng(items, group_id, mpk, &mut OsRng)
+    }
+    
+    /// Encrypt many `(message, label)` pairs using a caller-supplied RNG
+    ///
+    /// Each item gets its own RNG seeded from `rng` up front, so the output
+    /// depends only on `rng` and not on how the work is scheduled.
+    pub fn encrypt_many_labeled_with_rng<R: RngCore + CryptoRng>(
+        items: &[(&[u8], &[u8])],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+        rng: &mut R,
+    ) -> Result<Vec<Ciphertext>> {
+        let seeded: Vec<(&[u8], &[u8], [u8; 32])> = items
+            .iter()
+            .map(|&(message, label)| {
+                let mut seed = [0u8; 32];
+                rng.fill_bytes(&mut seed);
+                (message, label, seed)
+            })
+            .collect();
+        
+        map_ordered(&seeded, |&(message, label, seed)| {
+            Self::encrypt_labeled_with_rng(message, label, group_id, mpk, &mut StdRng::from_seed(seed))
+        })
+        .into_iter()
+        .collect()
+    }
+    
     /// Generate a decryption share
     pub fn share_decrypt(
         ciphertext: &Ciphertext,
         private_share: &PrivateKeyShare,
     ) -> Result<DecryptionShare> {
-        let secp = Secp256k1::new();
-        
-        // Verify ciphertext integrity: δ * P = D + H3(D, E, F) * E
-        let delta_key = SecretKey::from_slice(&ciphertext.delta.to_be_bytes())?;
-        let delta_point = PublicKey::from_secret_key(&secp, &delta_key);
-        let h3_val = hash_h3(&ciphertext.d, &ciphertext.e, &ciphertext.f);
-        let e_scaled = ciphertext.e.mul_tweak(&secp, &h3_val)?;
-        let expected = ciphertext.d.combine(&e_scaled)?;
-        
-        if delta_point != expected {
-            return Err(DIBTDError::InvalidCiphertext);
-        }
+        Self::share_decrypt_labeled(ciphertext, b"", private_share)
+    }
+    
+    /// Generate a decryption share for a ciphertext encrypted under `label`
+    ///
+    /// Fails with `InvalidCiphertext` if the ciphertext was encrypted under a
+    /// different label, and with `CiphersuiteMismatch` if it was encrypted
+    /// under another suite than the share belongs to.
+    pub fn share_decrypt_labeled(
+        ciphertext: &Ciphertext,
+        label: &[u8],
+        private_share: &PrivateKeyShare,
+    ) -> Result<DecryptionShare> {
+        private_share.ciphersuite.ensure_matches(&ciphertext.ciphersuite)?;
+        ciphertext.verify_labeled(label)?;
         
         // Compute Λi = Ψi * D
-        let lambda_i = ciphertext.d.mul_tweak(&secp, &private_share.psi_i)?;
+        let lambda_i = Curve::mul(&ciphertext.d, &private_share.psi_i)?;
+        let proof = ZKProof::prove_decryption_share(private_share, &ciphertext.d, &lambda_i)?;
         
         Ok(DecryptionShare {
             index: private_share.index,
             lambda_i,
+            proof: Some(proof),
+            ciphersuite: private_share.ciphersuite,
         })
     }
     
+    /// Generate this member's decryption shares for many ciphertexts
+    pub fn share_decrypt_many(
+        ciphertexts: &[Ciphertext],
+        private_share: &PrivateKeyShare,
+    ) -> Result<Vec<DecryptionShare>> {
+        map_ordered(ciphertexts, |ciphertext| Self::share_decrypt(ciphertext, private_share))
+            .into_iter()
+            .collect()
+    }
+    
+    /// Generate this member's decryption shares for many labeled ciphertexts
+    pub fn share_decrypt_many_labeled(
+        items: &[(&Ciphertext, &[u8])],
+        private_share: &PrivateKeyShare,
+    ) -> Result<Vec<DecryptionShare>> {
+        map_ordered(items, |&(ciphertext, label)| {
+            Self::share_decrypt_labeled(ciphertext, label, private_share)
+        })
+        .into_iter()
+        .collect()
+    }
+    
     /// Combine decryption shares to recover the message
     pub fn decrypt(
         ciphertext: &Ciphertext,
         shares: &[DecryptionShare],
         threshold: usize,
+    ) -> Result<Vec<u8>> {
+        Self::decrypt_labeled(ciphertext, b"", shares, threshold)
+    }
+    
+    /// Combine decryption shares for a ciphertext encrypted under `label`
+    pub fn decrypt_labeled(
+        ciphertext: &Ciphertext,
+        label: &[u8],
+        shares: &[DecryptionShare],
+        threshold: usize,
     ) -> Result<Vec<u8>> {
         if shares.len() < threshold {
             return Err(DIBTDError::InsufficientShares(shares.len(), threshold));
         }
         
-        let secp = Secp256k1::new();
-        let indices: Vec<usize> = shares.iter().map(|s| s.index).collect();
-        
-        // Compute Δ using Lagrange interpolation
-        let mut delta: Option<PublicKey> = None;
-        
-        for share in shares.iter().take(threshold) {
-            let coeff = crate::utils::lagrange_coefficient(&indices[..threshold], share.index, 0)?;
-            let weighted = share.lambda_i.mul_tweak(&secp, &coeff)?;
-            
-            delta = Some(match delta {
-                None => weighted,
-                Some(acc) => acc.combine(&weighted)?,
-            });
+        let indices: Vec<usize> = shares.iter().take(threshold).map(|s| s.index).collect();
+        let quorum = Quorum::new(&indices)?;
+        Self::decrypt_labeled_with_quorum(ciphertext, label, &shares[..threshold], &quorum)
+    }
+    
+    /// Combine decryption shares from the members of a precomputed quorum
+    ///
+    /// Shares from members outside the quorum are ignored.
+    pub fn decrypt_with_quorum(
+        ciphertext: &Ciphertext,
+        shares: &[DecryptionShare],
+        quorum: &Quorum,
+    ) -> Result<Vec<u8>> {
+        Self::decrypt_labeled_with_quorum(ciphertext, b"", shares, quorum)
+    }
+    
+    /// Combine decryption shares for a labeled ciphertext using a precomputed quorum
+    ///
+    /// Fails with `CiphersuiteMismatch` if any share was produced by a key of
+    /// another suite than the ciphertext's.
+    pub fn decrypt_labeled_with_quorum(
+        ciphertext: &Ciphertext,
+        label: &[u8],
+        shares: &[DecryptionShare],
+        quorum: &Quorum,
+    ) -> Result<Vec<u8>> {
+        ciphertext.ciphersuite.ensure_supported()?;
+        for share in shares {
+            share.ciphersuite.ensure_matches(&ciphertext.ciphersuite)?;
         }
         
-        let delta = delta.ok_or(DIBTDError::DecryptionFailed)?;
+        // Compute Δ = Σ λ_i * Λ_i with one multi-scalar multiplication
+        let points: Vec<(usize, Point)> = shares.iter().map(|s| (s.index, s.lambda_i)).collect();
+        let delta = quorum.combine(&points)?;
         
-        // Compute Θ = H2(Δ)
-        let theta = hash_h2(&delta);
+        // Compute Θ = H2(Δ|L)
+        let suite = &ciphertext.ciphersuite;
+        let theta = derive_theta(&delta, &transcript_label(suite, label), suite);
         let theta_padded = pad_or_truncate(&theta, ciphertext.f.len());
         
         // Compute Ω = H2(E|Θ)
         let mut omega_input = Vec::new();
-        omega_input.extend_from_slice(&ciphertext.e.serialize());
+        omega_input.extend_from_slice(&Curve::point_to_bytes(&ciphertext.e));
         omega_input.extend_from_slice(&theta);
-        let omega = hash_h2_bytes(&omega_input);
+        let omega = hash_h2_bytes(&omega_input, suite);
         let omega_padded = pad_or_truncate(&omega, ciphertext.f.len());
         
         // Compute m = F ⊕ Θ ⊕ Ω  
@@ -143,15 +299,272 @@ impl DIBTDEncryption {
         // Verify: E = H1(m, Δ) * P
         let mut r_input = Vec::new();
         r_input.extend_from_slice(&message);
-        r_input.extend_from_slice(&delta.serialize());
-        let r = hash_h1(&r_input);
-        let r_key = SecretKey::from_slice(&r.to_be_bytes())?;
-        let expected_e = PublicKey::from_secret_key(&secp, &r_key);
+        r_input.extend_from_slice(&Curve::point_to_bytes(&delta));
+        let r = hash_h1(&r_input, suite);
+        let expected_e = Curve::mul_generator(&r)?;
         
         if expected_e != ciphertext.e {
-            return Err(DIBTDError::DecryptionFailed);
+            return Err(DIBTDError::DecryptionFailed { stage: Stage::Combination });
         }
         
         Ok(message)
     }
-}
\ No newline at end of file
+    
+    /// Check the proofs of the shares to be combined, then combine them
+    ///
+    /// `verification_keys` maps member indices to VK_i = ψ_i * P. The proofs
+    /// of the first `threshold` shares are checked together with one
+    /// multi-scalar multiplication before anything is combined; if the batch
+    /// fails, the first bad share is reported as `InvalidShare` at the
+    /// `ShareVerification` stage. A share without a proof or without a known
+    /// verification key counts as bad.
+    pub fn decrypt_verified(
+        ciphertext: &Ciphertext,
+        shares: &[DecryptionShare],
+        threshold: usize,
+        verification_keys: &HashMap<usize, Point>,
+    ) -> Result<Vec<u8>> {
+        Self::decrypt_labeled_verified(ciphertext, b"", shares, threshold, verification_keys)
+    }
+    
+    /// Check share proofs and combine the shares for a labeled ciphertext
+    pub fn decrypt_labeled_verified(
+        ciphertext: &Ciphertext,
+        label: &[u8],
+        shares: &[DecryptionShare],
+        threshold: usize,
+        verification_keys: &HashMap<usize, Point>,
+    ) -> Result<Vec<u8>> {
+        if shares.len() < threshold {
+            return Err(DIBTDError::InsufficientShares(shares.len(), threshold));
+        }
+        
+        let quorum = &shares[..threshold];
+        let mut items = Vec::with_capacity(threshold);
+        for share in quorum {
+            let verification_key = verification_keys.get(&share.index).ok_or(
+                DIBTDError::InvalidShare {
+                    index: share.index,
+                    stage: Stage::ShareVerification,
+                },
+            )?;
+            items.push((share, verification_key));
+        }
+        if let Some(&bad) = ZKProof::find_invalid_decryption_shares(ciphertext, &items).first() {
+            return Err(DIBTDError::InvalidShare {
+                index: quorum[bad].index,
+                stage: Stage::ShareVerification,
+            });
+        }
+        
+        Self::decrypt_labeled(ciphertext, label, shares, threshold)
+    }
+    
+    /// Decrypt many ciphertexts whose shares all come from the same quorum
+    pub fn decrypt_batch(
+        items: &[(&Ciphertext, &[DecryptionShare])],
+        quorum: &Quorum,
+    ) -> Result<Vec<Vec<u8>>> {
+        items
+            .iter()
+            .map(|&(ciphertext, shares)| Self::decrypt_with_quorum(ciphertext, shares, quorum))
+            .collect()
+    }
+    
+    /// Decrypt many labeled ciphertexts whose shares all come from the same quorum
+    pub fn decrypt_batch_labeled(
+        items: &[(&Ciphertext, &[u8], &[DecryptionShare])],
+        quorum: &Quorum,
+    ) -> Result<Vec<Vec<u8>>> {
+        items
+            .iter()
+            .map(|&(ciphertext, label, shares)| {
+                Self::decrypt_labeled_with_quorum(ciphertext, label, shares, quorum)
+            })
+            .collect()
+    }
+    
+    /// Produce a fresh, unlinkable ciphertext of the same message for the same group
+    ///
+    /// DIBTD ciphertexts cannot be re-randomized publicly: E = H1(m, Δ) * P
+    /// and the mask on F both depend on Δ, so changing D requires the message
+    /// and Δ, and a ciphertext that anyone could transform into another valid
+    /// one would break the CCA check at combination. Instead a quorum's
+    /// decryption shares recover the message, which is encrypted again under
+    /// new randomness. Whoever runs this sees the message, exactly like the
+    /// combiner in `decrypt`.
+    pub fn reencrypt(
+        ciphertext: &Ciphertext,
+        shares: &[DecryptionShare],
+        threshold: usize,
+        group_id: &str,
+        mpk: &MasterPublicKey,
+    ) -> Result<Ciphertext> {
+        Self::reencrypt_labeled_with_rng(
+            ciphertext, b"", shares, threshold, group_id, mpk, &mut OsRng,
+        )
+    }
+    
+    /// Re-encrypt a ciphertext encrypted under `label`, keeping the label
+    pub fn reencrypt_labeled(
+        ciphertext: &Ciphertext,
+        label: &[u8],
+        shares: &[DecryptionShare],
+        threshold: usize,
+        group_id: &str,
+        mpk: &MasterPublicKey,
+    ) -> Result<Ciphertext> {
+        Self::reencrypt_labeled_with_rng(
+            ciphertext, label, shares, threshold, group_id, mpk, &mut OsRng,
+        )
+    }
+    
+    /// Re-encrypt a labeled ciphertext using a caller-supplied RNG
+    ///
+    /// Fails with `CiphersuiteMismatch` unless the ciphertext is of the same
+    /// suite as `mpk`, so the copy keeps the suite of the original and a
+    /// re-encrypted envelope key still matches its payload.
+    pub fn reencrypt_labeled_with_rng<R: RngCore + CryptoRng>(
+        ciphertext: &Ciphertext,
+        label: &[u8],
+        shares: &[DecryptionShare],
+        threshold: usize,
+        group_id: &str,
+        mpk: &MasterPublicKey,
+        rng: &mut R,
+    ) -> Result<Ciphertext> {
+        mpk.params.ciphersuite.ensure_matches(&ciphertext.ciphersuite)?;
+        let message = Self::decrypt_labeled(ciphertext, label, shares, threshold)?;
+        
+        Self::encrypt_labeled_with_rng(&message, label, group_id, mpk, rng)
+    }
+}
+
+/// The label as hashed into Θ and H3: the encoded suite, length-prefixed,
+/// then L, so a ciphertext only checks and decrypts under the suite it
+/// records
+fn transcript_label(suite: &Ciphersuite, label: &[u8]) -> Vec<u8> {
+    let name = suite.to_string();
+    let mut bound = Vec::with_capacity(1 + name.len() + label.len());
+    bound.push(name.len() as u8);
+    bound.extend_from_slice(name.as_bytes());
+    bound.extend_from_slice(label);
+    bound
+}
+
+/// Θ = H2(Δ|L); Δ has a fixed length, so the label needs no delimiter
+fn derive_theta(delta: &Point, label: &[u8], suite: &Ciphersuite) -> Vec<u8> {
+    let mut input = Curve::point_to_bytes(delta);
+    input.extend_from_slice(label);
+    hash_h2_bytes(&input, suite)
+}
+
+impl Ciphertext {
+    /// Check the public well-formedness proof δ * P = D + H3(D, E, F, L) * E
+    /// for the empty label, where L is the label prefixed with the encoded
+    /// ciphersuite
+    ///
+    /// Needs no key material, so storage gateways and auditors can reject
+    /// malformed ciphertexts before any share is ever requested.
+    pub fn verify(&self) -> Result<()> {
+        self.verify_labeled(b"")
+    }
+    
+    /// Check the well-formedness proof of a ciphertext encrypted under `label`
+    pub fn verify_labeled(&self, label: &[u8]) -> Result<()> {
+        self.ciphersuite.ensure_supported()?;
+        
+        let delta_point = Curve::mul_generator(&self.delta)
+            .map_err(|_| DIBTDError::InvalidCiphertext)?;
+        let bound = transcript_label(&self.ciphersuite, label);
+        let h3_val = hash_h3(&self.d, &self.e, &self.f, &bound, &self.ciphersuite);
+        let e_scaled = Curve::mul(&self.e, &h3_val)
+            .map_err(|_| DIBTDError::InvalidCiphertext)?;
+        let expected = Curve::add(&self.d, &e_scaled)
+            .map_err(|_| DIBTDError::InvalidCiphertext)?;
+        
+        if delta_point != expected {
+            return Err(DIBTDError::InvalidCiphertext);
+        }
+        
+        Ok(())
+    }
+    
+    /// Verify many unlabeled ciphertexts at once
+    pub fn batch_verify(ciphertexts: &[Ciphertext]) -> Result<()> {
+        let labeled: Vec<(&Ciphertext, &[u8])> =
+            ciphertexts.iter().map(|c| (c, &b""[..])).collect();
+        Self::batch_verify_labeled_with_rng(&labeled, &mut OsRng)
+    }
+    
+    /// Verify many (ciphertext, label) pairs at once
+    pub fn batch_verify_labeled(ciphertexts: &[(&Ciphertext, &[u8])]) -> Result<()> {
+        Self::batch_verify_labeled_with_rng(ciphertexts, &mut OsRng)
+    }
+    
+    /// Verify many (ciphertext, label) pairs at once using a caller-supplied RNG
+    ///
+    /// Checks a random linear combination of the individual equations,
+    /// (Σ ρ_i δ_i) * P = Σ ρ_i * D_i + Σ ρ_i H3(D_i, E_i, F_i, L_i) * E_i, as a
+    /// single multi-scalar multiplication that must sum to the identity. It
+    /// holds for an invalid ciphertext only with negligible probability.
+    /// When it fails, each ciphertext is checked on its own and the first
+    /// invalid one is reported as `InvalidBatchCiphertext` with its position.
+    pub fn batch_verify_labeled_with_rng<R: RngCore + CryptoRng>(
+        ciphertexts: &[(&Ciphertext, &[u8])],
+        rng: &mut R,
+    ) -> Result<()> {
+        if ciphertexts.len() <= 1 || Self::combined_check(ciphertexts, rng).is_err() {
+            return Self::find_invalid(ciphertexts);
+        }
+        Ok(())
+    }
+    
+    /// Check every ciphertext individually, reporting the first invalid position
+    fn find_invalid(ciphertexts: &[(&Ciphertext, &[u8])]) -> Result<()> {
+        for (index, &(ciphertext, label)) in ciphertexts.iter().enumerate() {
+            match ciphertext.verify_labeled(label) {
+                Err(DIBTDError::InvalidCiphertext) => {
+                    return Err(DIBTDError::InvalidBatchCiphertext { index });
+                }
+                result => result?,
+            }
+        }
+        Ok(())
+    }
+    
+    /// The random linear combination of all the individual equations
+    fn combined_check<R: RngCore + CryptoRng>(
+        ciphertexts: &[(&Ciphertext, &[u8])],
+        rng: &mut R,
+    ) -> Result<()> {
+        let mut delta_sum = Curve::SCALAR_ZERO;
+        let mut terms = Vec::with_capacity(2 * ciphertexts.len());
+        
+        for &(ciphertext, label) in ciphertexts {
+            ciphertext.ciphersuite.ensure_supported()?;
+            let rho = Curve::scalar_random(&mut *rng);
+            let bound = transcript_label(&ciphertext.ciphersuite, label);
+            let h3_val = hash_h3(
+                &ciphertext.d,
+                &ciphertext.e,
+                &ciphertext.f,
+                &bound,
+                &ciphertext.ciphersuite,
+            );
+            
+            delta_sum = scalar_add(&delta_sum, &scalar_mul(&rho, &ciphertext.delta));
+            terms.push((ciphertext.d, rho));
+            terms.push((ciphertext.e, scalar_mul(&rho, &h3_val)));
+        }
+        
+        // -(Σ ρ_i δ_i) * P + Σ ρ_i * D_i + Σ ρ_i H3_i * E_i = O
+        let sum = multi_scalar_mul_with_base(&scalar_negate(&delta_sum), &terms)
+            .map_err(|_| DIBTDError::InvalidCiphertext)?;
+        if sum.is_some() {
+            return Err(DIBTDError::InvalidCiphertext);
+        }
+        
+        Ok(())
+    }
+}
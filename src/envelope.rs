@@ -0,0 +1,386 @@
+use crate::{
+    aead::{AEADCipher, AeadAlgorithm, AeadParams, SEED_LEN},
+    ciphersuite::Ciphersuite,
+    encryption::DIBTDEncryption,
+    errors::{DIBTDError, Result},
+    stream::{prefix_len, StreamDecryptor, StreamEncryptor, DEFAULT_CHUNK_SIZE},
+    types::*,
+};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use std::io::{self, Read, Write};
+
+pub struct EnvelopeEncryption;
+
+impl EnvelopeEncryption {
+    /// Encrypt data under a fresh AEAD key and encapsulate the key for a group
+    ///
+    /// Returns the DIBTD-encapsulated seed together with the key-committing
+    /// `AEADPacket`; the AEAD key and nonce are derived from the seed. The
+    /// associated data doubles as the DIBTD
+    /// label, so decryption shares for the encapsulated key must be produced
+    /// with `DIBTDEncryption::share_decrypt_labeled` under the same bytes.
+    pub fn seal(
+        plaintext: &[u8],
+        associated_data: &[u8],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+    ) -> Result<(Ciphertext, AEADPacket)> {
+        Self::seal_with_rng(plaintext, associated_data, group_id, mpk, &mut OsRng)
+    }
+
+    /// Envelope-encrypt data for a group using a caller-supplied RNG
+    pub fn seal_with_rng<R: RngCore + CryptoRng>(
+        plaintext: &[u8],
+        associated_data: &[u8],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+        rng: &mut R,
+    ) -> Result<(Ciphertext, AEADPacket)> {
+        let (mut keys, packet) =
+            Self::seal_for_groups_with_rng(plaintext, associated_data, &[group_id], mpk, rng)?;
+        Ok((keys.remove(0), packet))
+    }
+
+    /// Encrypt data once and encapsulate the same AEAD key for several groups
+    ///
+    /// The encapsulated keys are returned in the order of `group_ids`; any one
+    /// of them opens the packet.
+    pub fn seal_for_groups(
+        plaintext: &[u8],
+        associated_data: &[u8],
+        group_ids: &[&str],
+        mpk: &MasterPublicKey,
+    ) -> Result<(Vec<Ciphertext>, AEADPacket)> {
+        Self::seal_for_groups_with_rng(plaintext, associated_data, group_ids, mpk, &mut OsRng)
+    }
+
+    /// Envelope-encrypt data for several groups using a caller-supplied RNG
+    ///
+    /// DIBTD encapsulates only a 32-byte seed, the size of its H2 mask; key
+    /// and nonce come from `AEADCipher::derive_from_seed` under the AEAD
+    /// algorithm of the system's ciphersuite, which the packet records.
+    /// The packet is key-committing (see `AEADCipher::encrypt_committing`),
+    /// so no group can be handed a seed that opens it to a different
+    /// plaintext, and `open` refuses a packet sealed with another algorithm
+    /// than the encapsulated key's suite names.
+    pub fn seal_for_groups_with_rng<R: RngCore + CryptoRng>(
+        plaintext: &[u8],
+        associated_data: &[u8],
+        group_ids: &[&str],
+        mpk: &MasterPublicKey,
+        rng: &mut R,
+    ) -> Result<(Vec<Ciphertext>, AEADPacket)> {
+        let algorithm = mpk.params.ciphersuite.aead;
+        let (seed, packet) = Self::seal_packet(plaintext, associated_data, algorithm, rng)?;
+
+        let encapsulated_keys = group_ids
+            .iter()
+            .map(|group_id| {
+                DIBTDEncryption::encrypt_labeled_with_rng(
+                    &seed,
+                    associated_data,
+                    group_id,
+                    mpk,
+                    rng,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok((encapsulated_keys, packet))
+    }
+
+    /// Seal data for a group as a self-describing `Envelope`
+    ///
+    /// Uses the AEAD algorithm of the system's ciphersuite.
+    pub fn seal_envelope(
+        plaintext: &[u8],
+        associated_data: &[u8],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+    ) -> Result<Envelope> {
+        Self::seal_envelope_with_rng(plaintext, associated_data, group_id, mpk, &mut OsRng)
+    }
+
+    /// Seal an `Envelope` using a caller-supplied RNG
+    pub fn seal_envelope_with_rng<R: RngCore + CryptoRng>(
+        plaintext: &[u8],
+        associated_data: &[u8],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+        rng: &mut R,
+    ) -> Result<Envelope> {
+        let algorithm = mpk.params.ciphersuite.aead;
+        let (seed, packet) = Self::seal_packet(plaintext, associated_data, algorithm, rng)?;
+        let encapsulated_key =
+            DIBTDEncryption::encrypt_labeled_with_rng(&seed, associated_data, group_id, mpk, rng)?;
+
+        Ok(Envelope {
+            encapsulated_key,
+            packet,
+        })
+    }
+
+    /// Combine decryption shares to recover the seed and open an `Envelope`
+    pub fn open_envelope(
+        envelope: &Envelope,
+        shares: &[DecryptionShare],
+        threshold: usize,
+    ) -> Result<Vec<u8>> {
+        Self::open(
+            &envelope.encapsulated_key,
+            &envelope.packet,
+            &envelope.packet.associated_data,
+            shares,
+            threshold,
+        )
+    }
+
+    /// Open an `Envelope` with a quorum's shares and seal its contents again
+    ///
+    /// Gives an unlinkable copy, e.g. for a backup site: the seed, nonce,
+    /// payload and encapsulated key are all fresh, while the associated data
+    /// and AEAD algorithm are kept. Re-encrypting only the encapsulated key
+    /// would leave the payload identical across copies (see
+    /// `DIBTDEncryption::reencrypt`).
+    pub fn reseal_envelope(
+        envelope: &Envelope,
+        shares: &[DecryptionShare],
+        threshold: usize,
+        group_id: &str,
+        mpk: &MasterPublicKey,
+    ) -> Result<Envelope> {
+        Self::reseal_envelope_with_rng(envelope, shares, threshold, group_id, mpk, &mut OsRng)
+    }
+
+    /// Reseal an `Envelope` using a caller-supplied RNG
+    pub fn reseal_envelope_with_rng<R: RngCore + CryptoRng>(
+        envelope: &Envelope,
+        shares: &[DecryptionShare],
+        threshold: usize,
+        group_id: &str,
+        mpk: &MasterPublicKey,
+        rng: &mut R,
+    ) -> Result<Envelope> {
+        mpk.params
+            .ciphersuite
+            .ensure_matches(&envelope.encapsulated_key.ciphersuite)?;
+        let plaintext = Self::open_envelope(envelope, shares, threshold)?;
+        Self::seal_envelope_with_rng(
+            &plaintext,
+            &envelope.packet.associated_data,
+            group_id,
+            mpk,
+            rng,
+        )
+    }
+
+    /// Draw a seed and encrypt `plaintext` under the key and nonce derived from it
+    fn seal_packet<R: RngCore + CryptoRng>(
+        plaintext: &[u8],
+        associated_data: &[u8],
+        algorithm: AeadAlgorithm,
+        rng: &mut R,
+    ) -> Result<([u8; SEED_LEN], AEADPacket)> {
+        let mut seed = [0u8; SEED_LEN];
+        rng.fill_bytes(&mut seed);
+        let (key, nonce) = AEADCipher::derive_from_seed(&seed, algorithm)?;
+        let packet =
+            AEADCipher::encrypt_packet(algorithm, &key, &nonce, plaintext, associated_data)?;
+        Ok((seed, packet))
+    }
+
+    /// Combine decryption shares to recover the AEAD key and open the packet
+    ///
+    /// The packet must have been sealed over `associated_data`. Only
+    /// key-committing packets with an encapsulated seed are accepted; any
+    /// other secret fails with `InvalidCiphertext`. Envelopes that carry
+    /// packed parameters open with `open_legacy`.
+    pub fn open(
+        encapsulated_key: &Ciphertext,
+        packet: &AEADPacket,
+        associated_data: &[u8],
+        shares: &[DecryptionShare],
+        threshold: usize,
+    ) -> Result<Vec<u8>> {
+        if packet.associated_data != associated_data {
+            return Err(DIBTDError::AEADError(
+                "Packet was sealed over other associated data".to_string(),
+            ));
+        }
+        ensure_payload_algorithm(encapsulated_key, packet.algorithm)?;
+        let secret =
+            DIBTDEncryption::decrypt_labeled(encapsulated_key, associated_data, shares, threshold)?;
+        let seed = <&[u8; SEED_LEN]>::try_from(secret.as_slice())
+            .map_err(|_| DIBTDError::InvalidCiphertext)?;
+
+        let (key, nonce) = AEADCipher::derive_from_seed(seed, packet.algorithm)?;
+        if nonce != packet.nonce {
+            return Err(DIBTDError::AEADError(
+                "Packet nonce does not match the encapsulated seed".to_string(),
+            ));
+        }
+        AEADCipher::decrypt_packet(&key, packet)
+    }
+
+    /// Open an envelope sealed before seed encapsulation, whose encapsulated
+    /// secret is packed AEAD parameters
+    ///
+    /// The packed parameters say whether the payload is key-committing, and
+    /// whoever sealed the envelope chose them, so a payload opened here may
+    /// read differently for each group it was sealed to. Use only to migrate
+    /// records that predate `open`.
+    pub fn open_legacy(
+        encapsulated_key: &Ciphertext,
+        payload: &[u8],
+        associated_data: &[u8],
+        shares: &[DecryptionShare],
+        threshold: usize,
+    ) -> Result<Vec<u8>> {
+        let secret =
+            DIBTDEncryption::decrypt_labeled(encapsulated_key, associated_data, shares, threshold)?;
+        AeadParams::unpack(&secret)?.decrypt(payload, associated_data)
+    }
+
+    /// Stream-encrypt everything from `reader` into `writer` and encapsulate the key
+    ///
+    /// Memory use is bounded by the chunk size, so this suits multi-gigabyte
+    /// imaging studies. As with `seal`, only a seed is encapsulated; the
+    /// stream key and nonce prefix are derived from it under the algorithm
+    /// id recorded in the stream header.
+    pub fn seal_stream<Rd: Read, Wr: Write>(
+        reader: Rd,
+        writer: Wr,
+        associated_data: &[u8],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+    ) -> Result<Ciphertext> {
+        Self::seal_stream_with_rng(reader, writer, associated_data, group_id, mpk, &mut OsRng)
+    }
+
+    /// Stream-encrypt for a group using a caller-supplied RNG
+    ///
+    /// The stream uses the AEAD algorithm of the system's ciphersuite.
+    pub fn seal_stream_with_rng<Rd: Read, Wr: Write, R: RngCore + CryptoRng>(
+        mut reader: Rd,
+        writer: Wr,
+        associated_data: &[u8],
+        group_id: &str,
+        mpk: &MasterPublicKey,
+        rng: &mut R,
+    ) -> Result<Ciphertext> {
+        let algorithm = mpk.params.ciphersuite.aead;
+        let mut seed = [0u8; SEED_LEN];
+        rng.fill_bytes(&mut seed);
+        let (key, mut nonce) = AEADCipher::derive_from_seed(&seed, algorithm)?;
+        nonce.truncate(prefix_len(algorithm));
+
+        let mut encryptor = StreamEncryptor::with_prefix(
+            algorithm,
+            &key,
+            associated_data,
+            DEFAULT_CHUNK_SIZE,
+            nonce,
+            writer,
+        )?;
+        io::copy(&mut reader, &mut encryptor).map_err(|e| DIBTDError::AEADError(e.to_string()))?;
+        encryptor.finish()?;
+
+        DIBTDEncryption::encrypt_labeled_with_rng(&seed, associated_data, group_id, mpk, rng)
+    }
+
+    /// Recover the stream key from decryption shares and decrypt `reader` into `writer`
+    ///
+    /// Returns the number of plaintext bytes written. Every chunk is
+    /// authenticated before it is written; on error, what was written so far
+    /// is a prefix of the plaintext and should be discarded. The header's key
+    /// commitment and nonce prefix are checked against the derived key before
+    /// any chunk is decrypted, so the stream opens under this key only. A
+    /// secret other than a seed fails with `InvalidCiphertext`; streams whose
+    /// encapsulated secret is packed parameters open with `open_stream_legacy`.
+    pub fn open_stream<Rd: Read, Wr: Write>(
+        encapsulated_key: &Ciphertext,
+        mut reader: Rd,
+        mut writer: Wr,
+        associated_data: &[u8],
+        shares: &[DecryptionShare],
+        threshold: usize,
+    ) -> Result<u64> {
+        let secret =
+            DIBTDEncryption::decrypt_labeled(encapsulated_key, associated_data, shares, threshold)?;
+        let seed = <&[u8; SEED_LEN]>::try_from(secret.as_slice())
+            .map_err(|_| DIBTDError::InvalidCiphertext)?;
+        let copy_err = |e: io::Error| DIBTDError::AEADError(e.to_string());
+
+        // Peek at the version and algorithm id to derive the key, then hand
+        // the whole header to the decryptor, which checks its key commitment
+        let mut head = [0u8; 2];
+        reader.read_exact(&mut head).map_err(copy_err)?;
+        let algorithm = AeadAlgorithm::from_id(head[1])?;
+        ensure_payload_algorithm(encapsulated_key, algorithm)?;
+        let (key, nonce) = AEADCipher::derive_from_seed(seed, algorithm)?;
+
+        let mut decryptor = StreamDecryptor::new(&key, associated_data, (&head[..]).chain(reader))?;
+        if *decryptor.nonce_prefix() != nonce[..prefix_len(algorithm)] {
+            return Err(stream_mismatch());
+        }
+        let written = io::copy(&mut decryptor, &mut writer).map_err(copy_err)?;
+        writer.flush().map_err(copy_err)?;
+
+        Ok(written)
+    }
+
+    /// Decrypt a stream sealed before seed encapsulation, whose encapsulated
+    /// secret is packed parameters: the prefix zero-padded in the nonce slot
+    /// and the final chunk tag
+    ///
+    /// As with `open_legacy`, nothing commits the stream to the key, so use
+    /// only to migrate records that predate `open_stream`.
+    pub fn open_stream_legacy<Rd: Read, Wr: Write>(
+        encapsulated_key: &Ciphertext,
+        reader: Rd,
+        mut writer: Wr,
+        associated_data: &[u8],
+        shares: &[DecryptionShare],
+        threshold: usize,
+    ) -> Result<u64> {
+        let secret =
+            DIBTDEncryption::decrypt_labeled(encapsulated_key, associated_data, shares, threshold)?;
+        let params = AeadParams::unpack(&secret)?;
+        let copy_err = |e: io::Error| DIBTDError::AEADError(e.to_string());
+
+        let mut decryptor = StreamDecryptor::new(&params.key, associated_data, reader)?;
+        let prefix = decryptor.nonce_prefix();
+        if decryptor.algorithm() != params.algorithm || *prefix != params.nonce[..prefix.len()] {
+            return Err(stream_mismatch());
+        }
+
+        let written = io::copy(&mut decryptor, &mut writer).map_err(copy_err)?;
+        if decryptor.final_tag() != Some(params.tag) {
+            return Err(stream_mismatch());
+        }
+        writer.flush().map_err(copy_err)?;
+
+        Ok(written)
+    }
+}
+
+fn stream_mismatch() -> DIBTDError {
+    DIBTDError::AEADError("Stream does not match the encapsulated key".to_string())
+}
+
+/// Fail with `CiphersuiteMismatch` if a payload was sealed with another AEAD
+/// algorithm than the encapsulated key's suite names
+fn ensure_payload_algorithm(encapsulated_key: &Ciphertext, algorithm: AeadAlgorithm) -> Result<()> {
+    let expected = encapsulated_key.ciphersuite;
+    if expected.aead != algorithm {
+        return Err(DIBTDError::CiphersuiteMismatch {
+            expected: expected.to_string(),
+            found: Ciphersuite {
+                aead: algorithm,
+                ..expected
+            }
+            .to_string(),
+        });
+    }
+    Ok(())
+}
@@ -1,5 +1,36 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use thiserror::Error;
 
+/// Stage of the threshold decryption pipeline at which an error occurred
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    KeyGeneration,
+    CiphertextValidation,
+    ShareVerification,
+    Combination,
+    Aead,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::KeyGeneration => "key_generation",
+            Stage::CiphertextValidation => "ciphertext_validation",
+            Stage::ShareVerification => "share_verification",
+            Stage::Combination => "combination",
+            Stage::Aead => "aead",
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum DIBTDError {
     #[error("Invalid threshold parameters: t={0}, n={1}")]
@@ -8,14 +39,17 @@ pub enum DIBTDError {
     #[error("Insufficient shares for reconstruction: got {0}, need {1}")]
     InsufficientShares(usize, usize),
     
-    #[error("Invalid share verification")]
-    InvalidShareVerification,
+    #[error("Duplicate share index {0}")]
+    DuplicateIndex(usize),
+    
+    #[error("Invalid decryption share from member {index} during {stage}")]
+    InvalidShare { index: usize, stage: Stage },
     
     #[error("Invalid proof")]
     InvalidProof,
     
-    #[error("Decryption failed")]
-    DecryptionFailed,
+    #[error("Decryption failed during {stage}")]
+    DecryptionFailed { stage: Stage },
     
     #[error("Key generation failed")]
     KeyGenerationFailed,
@@ -23,9 +57,15 @@ pub enum DIBTDError {
     #[error("Invalid ciphertext")]
     InvalidCiphertext,
     
+    #[error("Invalid ciphertext at batch position {index}")]
+    InvalidBatchCiphertext { index: usize },
+    
     #[error("Secp256k1 error: {0}")]
     Secp256k1Error(#[from] secp256k1::Error),
     
+    #[error("Curve error: {0}")]
+    CurveError(String),
+    
     #[error("Serialization error: {0}")]
     SerializationError(String),
     
@@ -37,6 +77,88 @@ pub enum DIBTDError {
     
     #[error("DKG protocol failed: {0}")]
     DKGProtocolFailed(String),
+    
+    #[error("Record store error: {0}")]
+    StorageError(String),
+    
+    #[error("Record not found: {0}")]
+    RecordNotFound(String),
+    
+    #[error("FHIR resource error: {0}")]
+    FhirError(String),
+    
+    #[error("Break-glass access denied: {0}")]
+    BreakGlassDenied(String),
+    
+    #[error("Consent check failed: {0}")]
+    ConsentDenied(String),
+    
+    #[error("Unsupported ciphersuite: {0}")]
+    UnsupportedCiphersuite(String),
+    
+    #[error("Ciphersuite mismatch: expected {expected}, found {found}")]
+    CiphersuiteMismatch { expected: String, found: String },
 }
 
-pub type Result<T> = std::result::Result<T, DIBTDError>;
\ No newline at end of file
+pub type Result<T> = std::result::Result<T, DIBTDError>;
+
+impl DIBTDError {
+    /// Stable machine-readable error code, e.g. for API responses
+    pub fn code(&self) -> &'static str {
+        match self {
+            DIBTDError::InvalidThreshold(..) => "invalid_threshold",
+            DIBTDError::InsufficientShares(..) => "insufficient_shares",
+            DIBTDError::DuplicateIndex(_) => "duplicate_index",
+            DIBTDError::InvalidShare { .. } => "invalid_share",
+            DIBTDError::InvalidProof => "invalid_proof",
+            DIBTDError::DecryptionFailed { .. } => "decryption_failed",
+            DIBTDError::KeyGenerationFailed => "key_generation_failed",
+            DIBTDError::InvalidCiphertext | DIBTDError::InvalidBatchCiphertext { .. } => {
+                "invalid_ciphertext"
+            }
+            DIBTDError::Secp256k1Error(_) | DIBTDError::CurveError(_) => "curve_error",
+            DIBTDError::SerializationError(_) => "serialization_error",
+            DIBTDError::AEADError(_) => "aead_error",
+            DIBTDError::InvalidGroupIdentity => "invalid_group_identity",
+            DIBTDError::DKGProtocolFailed(_) => "dkg_failed",
+            DIBTDError::StorageError(_) => "storage_error",
+            DIBTDError::RecordNotFound(_) => "record_not_found",
+            DIBTDError::FhirError(_) => "fhir_error",
+            DIBTDError::BreakGlassDenied(_) => "break_glass_denied",
+            DIBTDError::ConsentDenied(_) => "consent_denied",
+            DIBTDError::UnsupportedCiphersuite(_) => "unsupported_ciphersuite",
+            DIBTDError::CiphersuiteMismatch { .. } => "ciphersuite_mismatch",
+        }
+    }
+    
+    /// Pipeline stage the error belongs to, where one applies
+    pub fn stage(&self) -> Option<Stage> {
+        match self {
+            DIBTDError::InvalidThreshold(..)
+            | DIBTDError::KeyGenerationFailed
+            | DIBTDError::DKGProtocolFailed(_) => Some(Stage::KeyGeneration),
+            DIBTDError::InvalidCiphertext
+            | DIBTDError::InvalidBatchCiphertext { .. }
+            | DIBTDError::CiphersuiteMismatch { .. } => Some(Stage::CiphertextValidation),
+            DIBTDError::InvalidProof => Some(Stage::ShareVerification),
+            DIBTDError::InsufficientShares(..) | DIBTDError::DuplicateIndex(_) => {
+                Some(Stage::Combination)
+            }
+            DIBTDError::InvalidShare { stage, .. } | DIBTDError::DecryptionFailed { stage } => {
+                Some(*stage)
+            }
+            DIBTDError::AEADError(_) => Some(Stage::Aead),
+            _ => None,
+        }
+    }
+    
+    /// Participant or member index at fault, where one is known
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            DIBTDError::DuplicateIndex(index)
+            | DIBTDError::InvalidShare { index, .. }
+            | DIBTDError::InvalidBatchCiphertext { index } => Some(*index),
+            _ => None,
+        }
+    }
+}
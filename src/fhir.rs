@@ -0,0 +1,258 @@
+use crate::{
+    encryption::DIBTDEncryption,
+    envelope::EnvelopeEncryption,
+    errors::{DIBTDError, Result},
+    types::*,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Property name marking a JSON object as an encrypted field envelope
+pub const ENCRYPTED_FIELD_KEY: &str = "dibtdEncrypted";
+
+pub struct FhirFieldEncryption;
+
+impl FhirFieldEncryption {
+    /// Encrypt every field selected by the policy in place
+    ///
+    /// Paths are dot-separated property names. Arrays are traversed implicitly
+    /// (`name.family` selects the family name of every entry in `name`), and a
+    /// numeric segment selects a single array element. Paths that do not exist
+    /// in the resource are skipped. Returns the number of fields encrypted.
+    pub fn encrypt_resource(
+        resource: &mut Value,
+        policy: &FieldPolicy,
+        mpk: &MasterPublicKey,
+    ) -> Result<usize> {
+        let context = resource_context(resource)?;
+        let mut encrypted = 0;
+
+        for rule in &policy.rules {
+            let segments: Vec<&str> = rule.path.split('.').collect();
+            if segments.iter().any(|s| s.is_empty())
+                || segments[0] == "resourceType"
+                || segments[0] == "id"
+            {
+                return Err(DIBTDError::FhirError(format!(
+                    "Invalid field path: {}",
+                    rule.path
+                )));
+            }
+
+            encrypted += visit_path(resource, &segments, "", &mut |field, path| {
+                let plaintext = serde_json::to_vec(field)
+                    .map_err(|e| DIBTDError::SerializationError(e.to_string()))?;
+                let aad = field_aad(&context, path);
+                let (encapsulated_key, payload) =
+                    EnvelopeEncryption::seal(&plaintext, &aad, &rule.group.id, mpk)?;
+
+                let envelope = EncryptedField {
+                    group: rule.group.clone(),
+                    encapsulated_key,
+                    payload,
+                };
+                let mut wrapper = serde_json::Map::new();
+                wrapper.insert(
+                    ENCRYPTED_FIELD_KEY.to_string(),
+                    serde_json::to_value(envelope)
+                        .map_err(|e| DIBTDError::SerializationError(e.to_string()))?,
+                );
+                *field = Value::Object(wrapper);
+                Ok(())
+            })?;
+        }
+
+        Ok(encrypted)
+    }
+
+    /// Generate a group member's decryption shares for a resource's fields
+    ///
+    /// Covers every field encrypted for `group_id` and returns the shares
+    /// keyed by field path, to be collected from the group's members and
+    /// passed to `decrypt_resource`.
+    pub fn share_decrypt_resource(
+        resource: &Value,
+        group_id: &str,
+        private_share: &PrivateKeyShare,
+    ) -> Result<HashMap<String, DecryptionShare>> {
+        let context = resource_context(resource)?;
+        let mut shares = HashMap::new();
+
+        visit_encrypted_fields(resource, "", &mut |field, path| {
+            if field.group.id == group_id {
+                let share = DIBTDEncryption::share_decrypt_labeled(
+                    &field.encapsulated_key,
+                    &field_aad(&context, path),
+                    private_share,
+                )?;
+                shares.insert(path.to_string(), share);
+            }
+            Ok(())
+        })?;
+
+        Ok(shares)
+    }
+
+    /// Combine collected decryption shares and decrypt a resource's fields
+    ///
+    /// `shares` maps a field path to the decryption shares collected for that
+    /// field. Fields without shares are left untouched. Returns the number of
+    /// fields decrypted. On error the resource is left unchanged rather than
+    /// partly decrypted.
+    pub fn decrypt_resource(
+        resource: &mut Value,
+        shares: &HashMap<String, Vec<DecryptionShare>>,
+    ) -> Result<usize> {
+        let context = resource_context(resource)?;
+        let mut decrypted = resource.clone();
+        let count = decrypt_fields(&mut decrypted, "", &context, shares)?;
+        *resource = decrypted;
+        Ok(count)
+    }
+}
+
+fn resource_context(resource: &Value) -> Result<String> {
+    let object = resource
+        .as_object()
+        .ok_or_else(|| DIBTDError::FhirError("Resource must be a JSON object".to_string()))?;
+    let resource_type = object
+        .get("resourceType")
+        .and_then(Value::as_str)
+        .ok_or_else(|| DIBTDError::FhirError("Missing resourceType".to_string()))?;
+    let id = object.get("id").and_then(Value::as_str).unwrap_or("");
+
+    Ok(format!("{}/{}", resource_type, id))
+}
+
+/// Associated data binding a field's ciphertext to its resource and location
+fn field_aad(context: &str, path: &str) -> Vec<u8> {
+    format!("{}#{}", context, path).into_bytes()
+}
+
+fn join_path(parent: &str, segment: &str) -> String {
+    if parent.is_empty() {
+        segment.to_string()
+    } else {
+        format!("{}.{}", parent, segment)
+    }
+}
+
+fn as_encrypted_field(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Object(map) if map.len() == 1 => map.get(ENCRYPTED_FIELD_KEY),
+        _ => None,
+    }
+}
+
+fn visit_path(
+    value: &mut Value,
+    segments: &[&str],
+    path: &str,
+    f: &mut dyn FnMut(&mut Value, &str) -> Result<()>,
+) -> Result<usize> {
+    let Some((segment, rest)) = segments.split_first() else {
+        if as_encrypted_field(value).is_some() {
+            return Ok(0);
+        }
+        f(value, path)?;
+        return Ok(1);
+    };
+
+    match value {
+        Value::Array(items) => match segment.parse::<usize>() {
+            Ok(index) => match items.get_mut(index) {
+                Some(item) => visit_path(item, rest, &join_path(path, segment), f),
+                None => Ok(0),
+            },
+            Err(_) => {
+                let mut count = 0;
+                for (index, item) in items.iter_mut().enumerate() {
+                    count += visit_path(item, segments, &join_path(path, &index.to_string()), f)?;
+                }
+                Ok(count)
+            }
+        },
+        Value::Object(map) => match map.get_mut(*segment) {
+            Some(child) => visit_path(child, rest, &join_path(path, segment), f),
+            None => Ok(0),
+        },
+        _ => Ok(0),
+    }
+}
+
+fn parse_encrypted_field(envelope: &Value) -> Result<EncryptedField> {
+    serde_json::from_value(envelope.clone())
+        .map_err(|e| DIBTDError::SerializationError(e.to_string()))
+}
+
+fn visit_encrypted_fields(
+    value: &Value,
+    path: &str,
+    f: &mut dyn FnMut(&EncryptedField, &str) -> Result<()>,
+) -> Result<()> {
+    if let Some(envelope) = as_encrypted_field(value) {
+        return f(&parse_encrypted_field(envelope)?, path);
+    }
+
+    match value {
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                visit_encrypted_fields(item, &join_path(path, &index.to_string()), f)?;
+            }
+        }
+        Value::Object(map) => {
+            for (key, child) in map {
+                visit_encrypted_fields(child, &join_path(path, key), f)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn decrypt_fields(
+    value: &mut Value,
+    path: &str,
+    context: &str,
+    shares: &HashMap<String, Vec<DecryptionShare>>,
+) -> Result<usize> {
+    if let Some(envelope) = as_encrypted_field(value) {
+        let Some(field_shares) = shares.get(path) else {
+            return Ok(0);
+        };
+        let field = parse_encrypted_field(envelope)?;
+
+        let plaintext = EnvelopeEncryption::open(
+            &field.encapsulated_key,
+            &field.payload,
+            &field_aad(context, path),
+            field_shares,
+            field.group.threshold,
+        )?;
+
+        *value = serde_json::from_slice(&plaintext)
+            .map_err(|e| DIBTDError::SerializationError(e.to_string()))?;
+        return Ok(1);
+    }
+
+    let mut count = 0;
+    match value {
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                count += decrypt_fields(
+                    item,
+                    &join_path(path, &index.to_string()),
+                    context,
+                    shares,
+                )?;
+            }
+        }
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                count += decrypt_fields(child, &join_path(path, key), context, shares)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(count)
+}
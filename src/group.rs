@@ -0,0 +1,388 @@
+//! Prime-order groups the threshold scheme runs over
+//!
+//! `dkg`, `encryption`, `crypto`, `threshold` and `utils` only use the
+//! operations of the [`Group`] trait, through the [`Curve`] chosen at compile
+//! time: secp256k1 by default, NIST P-256 with the `p256` feature, or
+//! Ristretto255 with the `ristretto` feature. Scalars are encoded as 32
+//! big-endian bytes on every backend; points use the backend's compressed
+//! encoding. The identity is never a valid point: operations that would
+//! produce it fail, as they always have with libsecp256k1.
+
+use crate::ciphersuite::CurveId;
+use crate::errors::{DIBTDError, Result};
+use crate::msm;
+use rand::{CryptoRng, RngCore};
+use std::fmt::Debug;
+use std::sync::OnceLock;
+
+#[cfg(all(feature = "p256", feature = "ristretto"))]
+compile_error!("the `p256` and `ristretto` features select different curves; enable at most one");
+
+/// Backend selected by cargo features
+#[cfg(not(any(feature = "p256", feature = "ristretto")))]
+pub type Curve = Secp256k1;
+
+/// Backend selected by cargo features
+#[cfg(feature = "p256")]
+pub type Curve = P256;
+
+/// Backend selected by cargo features
+#[cfg(all(feature = "ristretto", not(feature = "p256")))]
+pub type Curve = Ristretto255;
+
+/// Scalar of the selected curve
+pub type Scalar = <Curve as Group>::Scalar;
+
+/// Point of the selected curve
+pub type Point = <Curve as Group>::Point;
+
+/// A prime-order group with a fixed generator G
+pub trait Group {
+    type Scalar: Copy + Debug + PartialEq + Eq + Send + Sync;
+    type Point: Copy + Debug + PartialEq + Eq + Send + Sync;
+
+    /// Curve as named in a `Ciphersuite` and in domain-separation tags
+    const ID: CurveId;
+    const SCALAR_ZERO: Self::Scalar;
+    const SCALAR_ONE: Self::Scalar;
+
+    /// Uniformly random scalar
+    fn scalar_random<R: RngCore + CryptoRng>(rng: &mut R) -> Self::Scalar;
+    fn scalar_from_u64(value: u64) -> Self::Scalar;
+    fn scalar_add(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    fn scalar_mul(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    fn scalar_negate(a: &Self::Scalar) -> Self::Scalar;
+    /// Multiplicative inverse modulo the group order; `None` for zero
+    fn scalar_invert(a: &Self::Scalar) -> Option<Self::Scalar>;
+    /// 32-byte big-endian encoding
+    fn scalar_to_bytes(a: &Self::Scalar) -> [u8; 32];
+    /// Parse a big-endian encoding, rejecting values at or above the group order
+    fn scalar_from_bytes(bytes: &[u8; 32]) -> Result<Self::Scalar>;
+    /// Reduce a 256-bit big-endian integer, e.g. a hash output, modulo the group order
+    fn scalar_reduce(bytes: &[u8; 32]) -> Self::Scalar;
+
+    /// s * G; fails for s = 0
+    fn mul_generator(s: &Self::Scalar) -> Result<Self::Point>;
+    /// s * P; fails if the product is the identity
+    fn mul(point: &Self::Point, s: &Self::Scalar) -> Result<Self::Point>;
+    /// P + Q; fails if the sum is the identity
+    fn add(p: &Self::Point, q: &Self::Point) -> Result<Self::Point>;
+    /// b * G + Σ s_i * P_i, or `None` if that is the identity
+    ///
+    /// May run in variable time, so only use it on public inputs.
+    fn multi_scalar_mul_with_base(
+        base: &Self::Scalar,
+        terms: &[(Self::Point, Self::Scalar)],
+    ) -> Result<Option<Self::Point>>;
+    /// Compressed point encoding
+    fn point_to_bytes(point: &Self::Point) -> Vec<u8>;
+    /// Decode a compressed point, rejecting the identity
+    fn point_from_bytes(bytes: &[u8]) -> Result<Self::Point>;
+}
+
+/// secp256k1 via libsecp256k1, with k256 for scalar arithmetic
+#[derive(Clone, Copy, Debug)]
+pub struct Secp256k1;
+
+pub(crate) fn secp256k1_context() -> &'static secp256k1::Secp256k1<secp256k1::All> {
+    static CONTEXT: OnceLock<secp256k1::Secp256k1<secp256k1::All>> = OnceLock::new();
+    CONTEXT.get_or_init(secp256k1::Secp256k1::new)
+}
+
+fn to_k256(a: &secp256k1::Scalar) -> k256::Scalar {
+    use k256::elliptic_curve::ops::Reduce;
+    <k256::Scalar as Reduce<k256::U256>>::reduce_bytes(&a.to_be_bytes().into())
+}
+
+fn from_k256(a: &k256::Scalar) -> secp256k1::Scalar {
+    secp256k1::Scalar::from_be_bytes(a.to_bytes().into()).expect("k256 scalars are reduced")
+}
+
+impl Group for Secp256k1 {
+    type Scalar = secp256k1::Scalar;
+    type Point = secp256k1::PublicKey;
+
+    const ID: CurveId = CurveId::Secp256k1;
+    const SCALAR_ZERO: Self::Scalar = secp256k1::Scalar::ZERO;
+    const SCALAR_ONE: Self::Scalar = secp256k1::Scalar::ONE;
+
+    fn scalar_random<R: RngCore + CryptoRng>(rng: &mut R) -> Self::Scalar {
+        secp256k1::Scalar::random_custom(rng)
+    }
+
+    fn scalar_from_u64(value: u64) -> Self::Scalar {
+        let mut bytes = [0u8; 32];
+        bytes[24..].copy_from_slice(&value.to_be_bytes());
+        secp256k1::Scalar::from_be_bytes(bytes).expect("u64 values are below the order")
+    }
+
+    fn scalar_add(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar {
+        from_k256(&(to_k256(a) + to_k256(b)))
+    }
+
+    fn scalar_mul(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar {
+        from_k256(&(to_k256(a) * to_k256(b)))
+    }
+
+    fn scalar_negate(a: &Self::Scalar) -> Self::Scalar {
+        from_k256(&-to_k256(a))
+    }
+
+    fn scalar_invert(a: &Self::Scalar) -> Option<Self::Scalar> {
+        Option::from(to_k256(a).invert()).map(|inverse| from_k256(&inverse))
+    }
+
+    fn scalar_to_bytes(a: &Self::Scalar) -> [u8; 32] {
+        a.to_be_bytes()
+    }
+
+    fn scalar_from_bytes(bytes: &[u8; 32]) -> Result<Self::Scalar> {
+        secp256k1::Scalar::from_be_bytes(*bytes)
+            .map_err(|_| DIBTDError::Secp256k1Error(secp256k1::Error::InvalidTweak))
+    }
+
+    fn scalar_reduce(bytes: &[u8; 32]) -> Self::Scalar {
+        use k256::elliptic_curve::ops::Reduce;
+        from_k256(&<k256::Scalar as Reduce<k256::U256>>::reduce_bytes(
+            &(*bytes).into(),
+        ))
+    }
+
+    fn mul_generator(s: &Self::Scalar) -> Result<Self::Point> {
+        let key = secp256k1::SecretKey::from_slice(&s.to_be_bytes())?;
+        Ok(secp256k1::PublicKey::from_secret_key(
+            secp256k1_context(),
+            &key,
+        ))
+    }
+
+    fn mul(point: &Self::Point, s: &Self::Scalar) -> Result<Self::Point> {
+        Ok(point.mul_tweak(secp256k1_context(), s)?)
+    }
+
+    fn add(p: &Self::Point, q: &Self::Point) -> Result<Self::Point> {
+        Ok(p.combine(q)?)
+    }
+
+    fn multi_scalar_mul_with_base(
+        base: &Self::Scalar,
+        terms: &[(Self::Point, Self::Scalar)],
+    ) -> Result<Option<Self::Point>> {
+        msm::secp256k1_multi_scalar_mul(base, terms)
+    }
+
+    fn point_to_bytes(point: &Self::Point) -> Vec<u8> {
+        point.serialize().to_vec()
+    }
+
+    fn point_from_bytes(bytes: &[u8]) -> Result<Self::Point> {
+        Ok(secp256k1::PublicKey::from_slice(bytes)?)
+    }
+}
+
+/// NIST P-256 via the p256 crate
+#[cfg(feature = "p256")]
+#[derive(Clone, Copy, Debug)]
+pub struct P256;
+
+#[cfg(feature = "p256")]
+impl Group for P256 {
+    type Scalar = p256::Scalar;
+    type Point = p256::ProjectivePoint;
+
+    const ID: CurveId = CurveId::P256;
+    const SCALAR_ZERO: Self::Scalar = p256::Scalar::ZERO;
+    const SCALAR_ONE: Self::Scalar = p256::Scalar::ONE;
+
+    fn scalar_random<R: RngCore + CryptoRng>(rng: &mut R) -> Self::Scalar {
+        <p256::Scalar as p256::elliptic_curve::Field>::random(rng)
+    }
+
+    fn scalar_from_u64(value: u64) -> Self::Scalar {
+        p256::Scalar::from(value)
+    }
+
+    fn scalar_add(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar {
+        a + b
+    }
+
+    fn scalar_mul(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar {
+        a * b
+    }
+
+    fn scalar_negate(a: &Self::Scalar) -> Self::Scalar {
+        -a
+    }
+
+    fn scalar_invert(a: &Self::Scalar) -> Option<Self::Scalar> {
+        Option::from(a.invert())
+    }
+
+    fn scalar_to_bytes(a: &Self::Scalar) -> [u8; 32] {
+        a.to_bytes().into()
+    }
+
+    fn scalar_from_bytes(bytes: &[u8; 32]) -> Result<Self::Scalar> {
+        use p256::elliptic_curve::PrimeField;
+        Option::from(p256::Scalar::from_repr((*bytes).into()))
+            .ok_or_else(|| DIBTDError::CurveError("Scalar out of range".to_string()))
+    }
+
+    fn scalar_reduce(bytes: &[u8; 32]) -> Self::Scalar {
+        use p256::elliptic_curve::ops::Reduce;
+        <p256::Scalar as Reduce<p256::U256>>::reduce_bytes(&(*bytes).into())
+    }
+
+    fn mul_generator(s: &Self::Scalar) -> Result<Self::Point> {
+        Self::mul(&p256::ProjectivePoint::GENERATOR, s)
+    }
+
+    fn mul(point: &Self::Point, s: &Self::Scalar) -> Result<Self::Point> {
+        p256_non_identity(point * s)
+    }
+
+    fn add(p: &Self::Point, q: &Self::Point) -> Result<Self::Point> {
+        p256_non_identity(p + q)
+    }
+
+    fn multi_scalar_mul_with_base(
+        base: &Self::Scalar,
+        terms: &[(Self::Point, Self::Scalar)],
+    ) -> Result<Option<Self::Point>> {
+        use p256::elliptic_curve::group::Group as _;
+        let sum = msm::p256_multi_scalar_mul(base, terms);
+        Ok((!bool::from(sum.is_identity())).then_some(sum))
+    }
+
+    fn point_to_bytes(point: &Self::Point) -> Vec<u8> {
+        use p256::elliptic_curve::sec1::ToEncodedPoint;
+        point.to_affine().to_encoded_point(true).as_bytes().to_vec()
+    }
+
+    fn point_from_bytes(bytes: &[u8]) -> Result<Self::Point> {
+        use p256::elliptic_curve::sec1::FromEncodedPoint;
+        let invalid = || DIBTDError::CurveError("Invalid point encoding".to_string());
+        let encoded = p256::EncodedPoint::from_bytes(bytes).map_err(|_| invalid())?;
+        let affine: Option<p256::AffinePoint> =
+            p256::AffinePoint::from_encoded_point(&encoded).into();
+        p256_non_identity(affine.ok_or_else(invalid)?.into())
+    }
+}
+
+#[cfg(feature = "p256")]
+fn p256_non_identity(point: p256::ProjectivePoint) -> Result<p256::ProjectivePoint> {
+    use p256::elliptic_curve::group::Group as _;
+    if bool::from(point.is_identity()) {
+        return Err(DIBTDError::CurveError("Point at infinity".to_string()));
+    }
+    Ok(point)
+}
+
+/// Ristretto255 via curve25519-dalek
+///
+/// Dalek encodes scalars little-endian; they are byte-reversed at this
+/// boundary so the rest of the crate sees the same big-endian encoding
+/// as on the other curves.
+#[cfg(feature = "ristretto")]
+#[derive(Clone, Copy, Debug)]
+pub struct Ristretto255;
+
+#[cfg(feature = "ristretto")]
+impl Group for Ristretto255 {
+    type Scalar = curve25519_dalek::Scalar;
+    type Point = curve25519_dalek::RistrettoPoint;
+
+    const ID: CurveId = CurveId::Ristretto255;
+    const SCALAR_ZERO: Self::Scalar = curve25519_dalek::Scalar::ZERO;
+    const SCALAR_ONE: Self::Scalar = curve25519_dalek::Scalar::ONE;
+
+    fn scalar_random<R: RngCore + CryptoRng>(rng: &mut R) -> Self::Scalar {
+        curve25519_dalek::Scalar::random(rng)
+    }
+
+    fn scalar_from_u64(value: u64) -> Self::Scalar {
+        curve25519_dalek::Scalar::from(value)
+    }
+
+    fn scalar_add(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar {
+        a + b
+    }
+
+    fn scalar_mul(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar {
+        a * b
+    }
+
+    fn scalar_negate(a: &Self::Scalar) -> Self::Scalar {
+        -a
+    }
+
+    fn scalar_invert(a: &Self::Scalar) -> Option<Self::Scalar> {
+        (*a != Self::SCALAR_ZERO).then(|| a.invert())
+    }
+
+    fn scalar_to_bytes(a: &Self::Scalar) -> [u8; 32] {
+        let mut bytes = a.to_bytes();
+        bytes.reverse();
+        bytes
+    }
+
+    fn scalar_from_bytes(bytes: &[u8; 32]) -> Result<Self::Scalar> {
+        let mut le = *bytes;
+        le.reverse();
+        Option::from(curve25519_dalek::Scalar::from_canonical_bytes(le))
+            .ok_or_else(|| DIBTDError::CurveError("Scalar out of range".to_string()))
+    }
+
+    fn scalar_reduce(bytes: &[u8; 32]) -> Self::Scalar {
+        let mut le = *bytes;
+        le.reverse();
+        curve25519_dalek::Scalar::from_bytes_mod_order(le)
+    }
+
+    fn mul_generator(s: &Self::Scalar) -> Result<Self::Point> {
+        ristretto_non_identity(curve25519_dalek::RistrettoPoint::mul_base(s))
+    }
+
+    fn mul(point: &Self::Point, s: &Self::Scalar) -> Result<Self::Point> {
+        ristretto_non_identity(point * s)
+    }
+
+    fn add(p: &Self::Point, q: &Self::Point) -> Result<Self::Point> {
+        ristretto_non_identity(p + q)
+    }
+
+    fn multi_scalar_mul_with_base(
+        base: &Self::Scalar,
+        terms: &[(Self::Point, Self::Scalar)],
+    ) -> Result<Option<Self::Point>> {
+        use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+        let sum = curve25519_dalek::RistrettoPoint::mul_base(base)
+            + curve25519_dalek::RistrettoPoint::vartime_multiscalar_mul(
+                terms.iter().map(|(_, s)| s),
+                terms.iter().map(|(p, _)| p),
+            );
+        Ok((!sum.is_identity()).then_some(sum))
+    }
+
+    fn point_to_bytes(point: &Self::Point) -> Vec<u8> {
+        point.compress().to_bytes().to_vec()
+    }
+
+    fn point_from_bytes(bytes: &[u8]) -> Result<Self::Point> {
+        let invalid = || DIBTDError::CurveError("Invalid point encoding".to_string());
+        let compressed = curve25519_dalek::ristretto::CompressedRistretto::from_slice(bytes)
+            .map_err(|_| invalid())?;
+        ristretto_non_identity(compressed.decompress().ok_or_else(invalid)?)
+    }
+}
+
+#[cfg(feature = "ristretto")]
+fn ristretto_non_identity(
+    point: curve25519_dalek::RistrettoPoint,
+) -> Result<curve25519_dalek::RistrettoPoint> {
+    use curve25519_dalek::traits::IsIdentity;
+    if point.is_identity() {
+        return Err(DIBTDError::CurveError("Point at infinity".to_string()));
+    }
+    Ok(point)
+}
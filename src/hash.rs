@@ -0,0 +1,154 @@
+//! Hashing to scalars with domain separation
+//!
+//! Follows `hash_to_field` from RFC 9380: the message is expanded with
+//! `expand_message_xmd` over SHA-256 to 48 bytes, 128 bits more than the
+//! group order, and reduced modulo the order. The result is uniform up to a
+//! 2^-128 bias and never fails, unlike parsing a 32-byte digest directly.
+//! Every use has its own domain-separation tag built from the ciphersuite id,
+//! so hashes for one purpose or curve can never be replayed as another.
+
+use crate::ciphersuite::Ciphersuite;
+use crate::errors::{DIBTDError, Result};
+use crate::group::{Curve, Group, Scalar};
+use sha2::{Digest, Sha256};
+
+/// Protocol and version part of the ciphersuite id
+pub const PROTOCOL_ID: &str = "DIBTD-V1";
+
+/// Bytes expanded per scalar: ceil((256 + 128) / 8)
+const SCALAR_EXPAND_LEN: usize = 48;
+
+const SHA256_BLOCK_LEN: usize = 64;
+const SHA256_OUTPUT_LEN: usize = 32;
+
+/// Ciphersuite id the domain-separation tags are derived from,
+/// e.g. `DIBTD-V1-secp256k1_XMD:SHA256`
+///
+/// Built from the suite's curve and hash; the AEAD algorithm plays no part
+/// in hashing to the group.
+pub fn ciphersuite_id(suite: &Ciphersuite) -> String {
+    format!("{}-{}_XMD:{}", PROTOCOL_ID, suite.curve.name(), suite.hash.name())
+}
+
+/// The places the scheme hashes into the scalar field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Domain {
+    /// H1: group identities and the encryption randomness r = H1(m, Δ)
+    H1,
+    /// H2: the 32-byte masks Θ and Ω
+    H2,
+    /// H3: the ciphertext well-formedness challenge
+    H3,
+    /// Schnorr proof-of-knowledge challenges
+    Schnorr,
+    /// Decryption share proof challenges
+    ShareProof,
+    /// Deterministic nonces for decryption share proofs
+    ShareNonce,
+}
+
+impl Domain {
+    /// Domain-separation tag under `suite`, e.g. `DIBTD-V1-secp256k1_XMD:SHA256_H1`
+    pub fn dst(self, suite: &Ciphersuite) -> Vec<u8> {
+        let tag = match self {
+            Domain::H1 => "H1",
+            Domain::H2 => "H2",
+            Domain::H3 => "H3",
+            Domain::Schnorr => "SCHNORR",
+            Domain::ShareProof => "SHARE_PROOF",
+            Domain::ShareNonce => "SHARE_NONCE",
+        };
+        format!("{}_{}", ciphersuite_id(suite), tag).into_bytes()
+    }
+}
+
+/// `expand_message_xmd` from RFC 9380, section 5.3.1, with SHA-256
+///
+/// Tags longer than 255 bytes are hashed first as the RFC prescribes.
+/// Fails if more than 8160 bytes are requested.
+pub fn expand_message_xmd(msg: &[u8], dst: &[u8], len_in_bytes: usize) -> Result<Vec<u8>> {
+    let ell = len_in_bytes.div_ceil(SHA256_OUTPUT_LEN);
+    if ell > 255 || len_in_bytes > u16::MAX as usize {
+        return Err(DIBTDError::CurveError(format!(
+            "Cannot expand a message to {} bytes",
+            len_in_bytes
+        )));
+    }
+    Ok(expand(msg, dst, len_in_bytes))
+}
+
+/// `expand_message_xmd` for lengths already checked against the limits
+fn expand(msg: &[u8], dst: &[u8], len_in_bytes: usize) -> Vec<u8> {
+    let ell = len_in_bytes.div_ceil(SHA256_OUTPUT_LEN);
+    let oversize;
+    let dst = if dst.len() > 255 {
+        oversize = Sha256::new()
+            .chain_update(b"H2C-OVERSIZE-DST-")
+            .chain_update(dst)
+            .finalize();
+        &oversize[..]
+    } else {
+        dst
+    };
+    let dst_prime = [dst, &[dst.len() as u8]].concat();
+
+    let b_0 = Sha256::new()
+        .chain_update([0u8; SHA256_BLOCK_LEN])
+        .chain_update(msg)
+        .chain_update((len_in_bytes as u16).to_be_bytes())
+        .chain_update([0u8])
+        .chain_update(&dst_prime)
+        .finalize();
+
+    let mut b_i = Sha256::new()
+        .chain_update(b_0)
+        .chain_update([1u8])
+        .chain_update(&dst_prime)
+        .finalize();
+    let mut uniform_bytes = b_i.to_vec();
+    for i in 2..=ell {
+        let mixed: Vec<u8> = b_0.iter().zip(b_i.iter()).map(|(a, b)| a ^ b).collect();
+        b_i = Sha256::new()
+            .chain_update(mixed)
+            .chain_update([i as u8])
+            .chain_update(&dst_prime)
+            .finalize();
+        uniform_bytes.extend_from_slice(&b_i);
+    }
+
+    uniform_bytes.truncate(len_in_bytes);
+    uniform_bytes
+}
+
+/// Hash `msg` to 32 uniform bytes under the domain-separation tag `dst`
+pub fn hash_to_bytes(msg: &[u8], dst: &[u8]) -> Vec<u8> {
+    expand(msg, dst, SHA256_OUTPUT_LEN)
+}
+
+/// Hash `msg` to a scalar under the domain-separation tag `dst`
+pub fn hash_to_scalar(msg: &[u8], dst: &[u8]) -> Scalar {
+    reduce_wide(&expand(msg, dst, SCALAR_EXPAND_LEN))
+}
+
+/// Reduce a 384-bit big-endian integer modulo the group order
+///
+/// Splits it as hi * 2^256 + lo; both halves fit in 256 bits, which every
+/// backend reduces exactly.
+fn reduce_wide(bytes: &[u8]) -> Scalar {
+    let (hi, lo) = bytes.split_at(bytes.len() - 32);
+    let mut hi_bytes = [0u8; 32];
+    hi_bytes[32 - hi.len()..].copy_from_slice(hi);
+    let mut lo_bytes = [0u8; 32];
+    lo_bytes.copy_from_slice(lo);
+
+    // 2^256 = (2^128)^2 modulo the order
+    let mut two_128 = [0u8; 32];
+    two_128[15] = 1;
+    let two_128 = Curve::scalar_reduce(&two_128);
+    let two_256 = Curve::scalar_mul(&two_128, &two_128);
+
+    Curve::scalar_add(
+        &Curve::scalar_mul(&Curve::scalar_reduce(&hi_bytes), &two_256),
+        &Curve::scalar_reduce(&lo_bytes),
+    )
+}
@@ -5,7 +5,17 @@ pub mod errors;
 pub mod types;
 pub mod utils;
 pub mod aead;
+pub mod stream;
 pub mod threshold;
+pub mod msm;
+pub mod group;
+pub mod hash;
+pub mod ciphersuite;
+pub mod envelope;
+pub mod store;
+pub mod fhir;
+pub mod breakglass;
+pub mod consent;
 
 pub use crypto::*;
 pub use dkg::*;
@@ -13,6 +23,12 @@ pub use encryption::*;
 pub use errors::*;
 pub use types::*;
 pub use threshold::*;
+pub use envelope::*;
+pub use store::*;
+pub use fhir::*;
+pub use breakglass::*;
+pub use consent::*;
+pub use ciphersuite::Ciphersuite;
 
 // Re-export commonly used utility functions
 pub use utils::{scalar_add, scalar_mul};
\ No newline at end of file
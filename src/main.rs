@@ -39,8 +39,8 @@ fn main() -> Result<()> {
     
     // Verify shares
     for i in 1..=n {
-        let valid = dkg.verify_shares(i)?;
-        println!("  - Node {} share verification: {}", i, if valid { "✓" } else { "✗" });
+        dkg.verify_shares(i)?;
+        println!("  - Node {} share verification: ✓", i);
     }
     
     // Finalize DKG to get master keys
@@ -65,36 +65,21 @@ fn main() -> Result<()> {
     // Patient's ePHR data
     let ehr_data = b"Patient: John Doe\nBlood Pressure: 120/80\nHeart Rate: 72 bpm\nDiagnosis: Normal";
     
-    // Generate AEAD key and encrypt the actual data
-    let aead_key = aead::AEADCipher::generate_key();
-    let nonce = aead::AEADCipher::generate_nonce();
+    // Encrypt the data under a key derived from a fresh seed, and the seed with DIBTD
     let associated_data = b"Medical Record 2024-01-15";
-    
-    let encrypted_ehr = aead::AEADCipher::encrypt(
-        &aead_key,
-        &nonce,
+    let envelope = EnvelopeEncryption::seal_envelope(
         ehr_data,
         associated_data,
-    )?;
-    
-    // Extract tag (last 16 bytes of AEAD ciphertext)
-    let tag_start = encrypted_ehr.len().saturating_sub(16);
-    let mut tag = [0u8; 16];
-    tag.copy_from_slice(&encrypted_ehr[tag_start..]);
-    
-    // Pack AEAD parameters for DIBTD encryption
-    let packed_params = aead::AEADCipher::pack_aead_params(&aead_key, &nonce, &tag);
-    
-    // Encrypt the AEAD parameters with DIBTD
-    let ciphertext = encryption::DIBTDEncryption::encrypt(
-        &packed_params,
         &group_id.id,
         &mpk,
     )?;
     
     println!("  - ePHR data encrypted successfully");
     println!("  - Original size: {} bytes", ehr_data.len());
-    println!("  - Encrypted size: {} bytes", encrypted_ehr.len());
+    println!(
+        "  - Encrypted size: {} bytes",
+        envelope.packet.ciphertext.len() + envelope.packet.tag.len()
+    );
     
     println!("\nStep 4: Threshold Decryption (Doctors)");
     
@@ -107,8 +92,9 @@ fn main() -> Result<()> {
     
     for &member_id in &participating_members {
         if let Some(private_share) = private_shares.get(&member_id) {
-            let dec_share = encryption::DIBTDEncryption::share_decrypt(
-                &ciphertext,
+            let dec_share = encryption::DIBTDEncryption::share_decrypt_labeled(
+                &envelope.encapsulated_key,
+                &envelope.packet.associated_data,
                 private_share,
             )?;
             
@@ -123,6 +109,7 @@ fn main() -> Result<()> {
                 &proof,
                 &private_share.verification_key,
                 "decryption_proof",
+                &mpk.params.ciphersuite,
             );
             
             println!("    - Member {} generated share: {}", member_id, if valid { "✓" } else { "✗" });
@@ -130,26 +117,13 @@ fn main() -> Result<()> {
         }
     }
     
-    // Combine shares to decrypt
-    let decrypted_params = encryption::DIBTDEncryption::decrypt(
-        &ciphertext,
+    // Combine shares to recover the seed and decrypt the packet
+    let decrypted_ehr = EnvelopeEncryption::open_envelope(
+        &envelope,
         &decryption_shares,
         group_threshold,
     )?;
     
-    // Unpack AEAD parameters
-    let (recovered_key, recovered_nonce, _recovered_tag) = 
-        aead::AEADCipher::unpack_aead_params(&decrypted_params)?;
-    
-    // Decrypt the actual ePHR data
-    // IMPORTANT: Pass the full encrypted_ehr (including tag) to decrypt
-    let decrypted_ehr = aead::AEADCipher::decrypt(
-        &recovered_key,
-        &recovered_nonce,
-        &encrypted_ehr,  // Pass full ciphertext with tag
-        associated_data,
-    )?;
-    
     println!("  - Decryption successful!");
     println!("\nRecovered ePHR Data:");
     println!("  {}", String::from_utf8_lossy(&decrypted_ehr));
@@ -158,7 +132,7 @@ fn main() -> Result<()> {
     println!("\nStep 5: Testing Security (Insufficient Shares)");
     let insufficient_shares = vec![decryption_shares[0].clone()];
     
-    match encryption::DIBTDEncryption::decrypt(&ciphertext, &insufficient_shares, group_threshold) {
+    match EnvelopeEncryption::open_envelope(&envelope, &insufficient_shares, group_threshold) {
         Err(DIBTDError::InsufficientShares(got, need)) => {
             println!("  - Security check passed: {} shares insufficient (need {})", got, need);
         }
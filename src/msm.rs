@@ -0,0 +1,236 @@
+use crate::errors::{DIBTDError, Result};
+use crate::group::{secp256k1_context, Curve, Group, Point, Scalar};
+use k256::{
+    elliptic_curve::{
+        ops::MulByGenerator,
+        sec1::{FromEncodedPoint, ToEncodedPoint},
+        PrimeField,
+    },
+    AffinePoint, EncodedPoint, ProjectivePoint,
+};
+use secp256k1::{PublicKey, SecretKey};
+use std::ops::AddAssign;
+
+/// From this many terms on Straus' method beats one libsecp256k1
+/// multiplication per term on secp256k1 (measured with the
+/// `multi_scalar_mul` criterion benchmark)
+const STRAUS_THRESHOLD: usize = 8;
+
+/// From this many terms on Pippenger beats Straus' method
+const PIPPENGER_THRESHOLD: usize = 64;
+
+/// Bits per Straus window; each point gets a table of 2^w - 1 multiples
+const STRAUS_WINDOW: usize = 4;
+
+/// Compute Σ s_i * P_i
+///
+/// On secp256k1, a handful of terms take one libsecp256k1 multiplication per
+/// term and a single combined addition; medium inputs use Straus'
+/// interleaved windows and large ones Pippenger's bucket method. Returns
+/// `None` if the sum is the point at infinity, which includes the empty sum.
+/// The Straus and Pippenger paths run in variable time, so only use this on
+/// public inputs such as ciphertexts, proofs, commitments and decryption
+/// shares.
+pub fn multi_scalar_mul(terms: &[(Point, Scalar)]) -> Result<Option<Point>> {
+    multi_scalar_mul_with_base(&Curve::SCALAR_ZERO, terms)
+}
+
+/// Compute b * G + Σ s_i * P_i, where G is the generator of the selected curve
+pub fn multi_scalar_mul_with_base(
+    base_scalar: &Scalar,
+    terms: &[(Point, Scalar)],
+) -> Result<Option<Point>> {
+    Curve::multi_scalar_mul_with_base(base_scalar, terms)
+}
+
+pub(crate) fn secp256k1_multi_scalar_mul(
+    base_scalar: &secp256k1::Scalar,
+    terms: &[(PublicKey, secp256k1::Scalar)],
+) -> Result<Option<PublicKey>> {
+    if terms.len() < STRAUS_THRESHOLD {
+        return per_term(base_scalar, terms);
+    }
+
+    let mut points = Vec::with_capacity(terms.len());
+    let mut scalars = Vec::with_capacity(terms.len());
+    for (point, scalar) in terms {
+        points.push(to_affine(point)?);
+        scalars.push(scalar.to_be_bytes());
+    }
+
+    let base = to_k256_scalar(base_scalar)?;
+    let sum = ProjectivePoint::mul_by_generator(&base)
+        + if terms.len() < PIPPENGER_THRESHOLD {
+            straus::<ProjectivePoint>(&points, &scalars)
+        } else {
+            pippenger::<ProjectivePoint, _>(&points, &scalars)
+        };
+    from_projective(&sum)
+}
+
+/// b * G + Σ s_i * P_i on P-256
+///
+/// A per-term multiplication here is itself a 4-bit window, so Straus'
+/// shared doublings win from two terms on; Pippenger takes over at the same
+/// threshold as on secp256k1.
+#[cfg(feature = "p256")]
+pub(crate) fn p256_multi_scalar_mul(
+    base_scalar: &p256::Scalar,
+    terms: &[(p256::ProjectivePoint, p256::Scalar)],
+) -> p256::ProjectivePoint {
+    let base = p256::ProjectivePoint::mul_by_generator(base_scalar);
+    if let [(point, scalar)] = terms {
+        return base + point * scalar;
+    }
+
+    let points: Vec<p256::AffinePoint> = terms.iter().map(|(point, _)| point.to_affine()).collect();
+    let scalars: Vec<[u8; 32]> = terms
+        .iter()
+        .map(|(_, scalar)| scalar.to_bytes().into())
+        .collect();
+    if terms.len() < PIPPENGER_THRESHOLD {
+        base + straus::<p256::ProjectivePoint>(&points, &scalars)
+    } else {
+        base + pippenger::<p256::ProjectivePoint, _>(&points, &scalars)
+    }
+}
+
+fn per_term(
+    base_scalar: &secp256k1::Scalar,
+    terms: &[(PublicKey, secp256k1::Scalar)],
+) -> Result<Option<PublicKey>> {
+    let secp = secp256k1_context();
+    let mut products = Vec::with_capacity(terms.len() + 1);
+
+    // Zero scalars contribute nothing and are rejected as tweaks
+    if *base_scalar != secp256k1::Scalar::ZERO {
+        let base_key = SecretKey::from_slice(&base_scalar.to_be_bytes())?;
+        products.push(PublicKey::from_secret_key(secp, &base_key));
+    }
+    for (point, scalar) in terms {
+        if *scalar != secp256k1::Scalar::ZERO {
+            products.push(point.mul_tweak(secp, scalar)?);
+        }
+    }
+
+    match products.len() {
+        0 => Ok(None),
+        1 => Ok(Some(products[0])),
+        // Only fails when the sum is the point at infinity
+        _ => Ok(PublicKey::combine_keys(&products.iter().collect::<Vec<_>>()).ok()),
+    }
+}
+
+/// Straus' interleaved-window method: a table of 1·P .. 15·P per point,
+/// normalized to affine with one inversion, and a single shared chain of
+/// doublings
+fn straus<P>(points: &[P::AffineRepr], scalars: &[[u8; 32]]) -> P
+where
+    P: k256::elliptic_curve::group::Curve + for<'a> AddAssign<&'a P::AffineRepr>,
+    P::AffineRepr: Copy + Default,
+{
+    let width = (1 << STRAUS_WINDOW) - 1;
+    let mut multiples = Vec::with_capacity(points.len() * width);
+    for point in points {
+        let mut multiple = P::identity();
+        for _ in 0..width {
+            multiple += point;
+            multiples.push(multiple);
+        }
+    }
+    let mut tables = vec![P::AffineRepr::default(); multiples.len()];
+    P::batch_normalize(&multiples, &mut tables);
+
+    let mut result = P::identity();
+    for w in (0..256 / STRAUS_WINDOW).rev() {
+        for _ in 0..STRAUS_WINDOW {
+            result = result.double();
+        }
+        for (table, scalar) in tables.chunks_exact(width).zip(scalars) {
+            let digit = window_digit(scalar, w * STRAUS_WINDOW, STRAUS_WINDOW);
+            if digit != 0 {
+                result += &table[digit - 1];
+            }
+        }
+    }
+    result
+}
+
+
+fn pippenger<P, A>(points: &[A], scalars: &[[u8; 32]]) -> P
+where
+    P: k256::elliptic_curve::group::Group + for<'a> AddAssign<&'a A>,
+{
+    let window = window_size(points.len());
+    let windows = 256usize.div_ceil(window);
+    let mut buckets = vec![P::identity(); (1 << window) - 1];
+    let mut result = P::identity();
+
+    for w in (0..windows).rev() {
+        for _ in 0..window {
+            result = result.double();
+        }
+
+        buckets.fill(P::identity());
+        for (point, scalar) in points.iter().zip(scalars) {
+            let digit = window_digit(scalar, w * window, window);
+            if digit != 0 {
+                buckets[digit - 1] += point;
+            }
+        }
+
+        // Σ d * B_d as a running sum from the highest bucket down
+        let mut running = P::identity();
+        let mut window_sum = P::identity();
+        for bucket in buckets.iter().rev() {
+            running += bucket;
+            window_sum += running;
+        }
+        result += window_sum;
+    }
+
+    result
+}
+
+fn window_size(n: usize) -> usize {
+    match n {
+        0..=3 => 2,
+        4..=15 => 3,
+        16..=63 => 4,
+        64..=255 => 5,
+        256..=1023 => 6,
+        _ => 7,
+    }
+}
+
+/// The `width` bits of a big-endian scalar starting at bit `start` (LSB = 0)
+fn window_digit(scalar: &[u8; 32], start: usize, width: usize) -> usize {
+    let mut digit = 0;
+    for i in 0..width {
+        let bit = start + i;
+        if bit < 256 && (scalar[31 - bit / 8] >> (bit % 8)) & 1 == 1 {
+            digit |= 1 << i;
+        }
+    }
+    digit
+}
+
+fn to_affine(point: &PublicKey) -> Result<AffinePoint> {
+    let encoded = EncodedPoint::from_bytes(point.serialize_uncompressed())
+        .map_err(|_| DIBTDError::Secp256k1Error(secp256k1::Error::InvalidPublicKey))?;
+    Option::from(AffinePoint::from_encoded_point(&encoded))
+        .ok_or(DIBTDError::Secp256k1Error(secp256k1::Error::InvalidPublicKey))
+}
+
+fn to_k256_scalar(scalar: &secp256k1::Scalar) -> Result<k256::Scalar> {
+    Option::from(k256::Scalar::from_repr(scalar.to_be_bytes().into()))
+        .ok_or(DIBTDError::Secp256k1Error(secp256k1::Error::InvalidTweak))
+}
+
+fn from_projective(point: &ProjectivePoint) -> Result<Option<PublicKey>> {
+    if *point == ProjectivePoint::IDENTITY {
+        return Ok(None);
+    }
+    let encoded = point.to_affine().to_encoded_point(false);
+    Ok(Some(PublicKey::from_slice(encoded.as_bytes())?))
+}
@@ -0,0 +1,358 @@
+use crate::{
+    breakglass::BreakGlassAccess,
+    encryption::DIBTDEncryption,
+    envelope::EnvelopeEncryption,
+    errors::{DIBTDError, Result},
+    group::Point,
+    types::*,
+    utils::to_hex,
+};
+use rand::{rngs::OsRng, RngCore};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Encrypted ePHR record store backed by a local directory
+///
+/// Each record is persisted as `records/<record_id>.json` and break-glass
+/// justification entries are appended to `audit/<record_id>.jsonl`. Patient
+/// and group indexes are rebuilt from disk when the store is opened and kept
+/// up to date on every insert.
+pub struct RecordStore {
+    root: PathBuf,
+    by_patient: HashMap<String, Vec<String>>,
+    by_group: HashMap<String, Vec<String>>,
+}
+
+impl RecordStore {
+    /// Open (or create) a record store rooted at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let root = path.as_ref().to_path_buf();
+        fs::create_dir_all(root.join("records")).map_err(storage_error)?;
+        fs::create_dir_all(root.join("audit")).map_err(storage_error)?;
+
+        let mut store = Self {
+            root,
+            by_patient: HashMap::new(),
+            by_group: HashMap::new(),
+        };
+
+        for entry in fs::read_dir(store.records_dir()).map_err(storage_error)? {
+            let path = entry.map_err(storage_error)?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let record = Self::read_record(&path)?;
+            store.index(&record);
+        }
+
+        Ok(store)
+    }
+
+    /// Encrypt and store a new record for the group named in its metadata
+    pub fn put(
+        &mut self,
+        metadata: RecordMetadata,
+        data: &[u8],
+        mpk: &MasterPublicKey,
+    ) -> Result<EncryptedRecord> {
+        self.put_record(metadata, None, data, mpk)
+    }
+
+    /// Encrypt and store a new record that can also be opened by break-glass
+    ///
+    /// The data key is additionally encapsulated to `emergency_group`, whose
+    /// threshold must exceed that of the record's group.
+    pub fn put_with_break_glass(
+        &mut self,
+        metadata: RecordMetadata,
+        emergency_group: &GroupIdentity,
+        data: &[u8],
+        mpk: &MasterPublicKey,
+    ) -> Result<EncryptedRecord> {
+        if emergency_group.threshold <= metadata.group.threshold {
+            return Err(DIBTDError::InvalidGroupIdentity);
+        }
+        self.put_record(metadata, Some(emergency_group), data, mpk)
+    }
+
+    fn put_record(
+        &mut self,
+        metadata: RecordMetadata,
+        emergency_group: Option<&GroupIdentity>,
+        data: &[u8],
+        mpk: &MasterPublicKey,
+    ) -> Result<EncryptedRecord> {
+        let mut id_bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut id_bytes);
+        let record_id = to_hex(&id_bytes);
+
+        let mut group_ids = vec![metadata.group.id.as_str()];
+        if let Some(group) = emergency_group {
+            group_ids.push(group.id.as_str());
+        }
+
+        let header = match emergency_group {
+            Some(group) => EnvelopeHeader {
+                mode: EnvelopeMode::BreakGlass,
+                emergency_group: Some(group.clone()),
+            },
+            None => EnvelopeHeader::default(),
+        };
+
+        let aad = record_aad(&record_id, &header, &metadata)?;
+        let (encapsulated_keys, payload) =
+            EnvelopeEncryption::seal_for_groups(data, &aad, &group_ids, mpk)?;
+        let mut encapsulated_keys = encapsulated_keys.into_iter();
+        let encapsulated_key = encapsulated_keys
+            .next()
+            .ok_or(DIBTDError::KeyGenerationFailed)?;
+        let emergency_key = encapsulated_keys.next();
+
+        let record = EncryptedRecord {
+            record_id,
+            header,
+            metadata,
+            stored_at: unix_timestamp(),
+            encapsulated_key,
+            emergency_key,
+            payload,
+        };
+
+        self.insert(record.clone())?;
+        Ok(record)
+    }
+
+    /// Store an already encrypted record, e.g. one replicated from another site
+    ///
+    /// Records whose encapsulated keys fail the public ciphertext check are
+    /// rejected with `InvalidCiphertext`.
+    pub fn insert(&mut self, record: EncryptedRecord) -> Result<()> {
+        let path = self.record_path(&record.record_id)?;
+        let aad = record.associated_data()?;
+        record.encapsulated_key.verify_labeled(&aad)?;
+        if let Some(emergency_key) = &record.emergency_key {
+            emergency_key.verify_labeled(&aad)?;
+        }
+        let json = serde_json::to_vec_pretty(&record)
+            .map_err(|e| DIBTDError::SerializationError(e.to_string()))?;
+
+        // Write to a temporary file first so a crash never leaves a torn record
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json).map_err(storage_error)?;
+        fs::rename(&tmp, &path).map_err(storage_error)?;
+
+        self.index(&record);
+        Ok(())
+    }
+
+    /// Load a record by id
+    pub fn get(&self, record_id: &str) -> Result<EncryptedRecord> {
+        let path = self.record_path(record_id)?;
+        if !path.exists() {
+            return Err(DIBTDError::RecordNotFound(record_id.to_string()));
+        }
+        Self::read_record(&path)
+    }
+
+    /// List all records of a patient, oldest first
+    pub fn list_by_patient(&self, patient_pseudonym: &str) -> Result<Vec<EncryptedRecord>> {
+        self.load_all(self.by_patient.get(patient_pseudonym))
+    }
+
+    /// List all records encrypted for a group, oldest first
+    pub fn list_by_group(&self, group_id: &str) -> Result<Vec<EncryptedRecord>> {
+        self.load_all(self.by_group.get(group_id))
+    }
+
+    /// Generate a group member's decryption share for a stored record
+    pub fn share_decrypt(
+        &self,
+        record_id: &str,
+        private_share: &PrivateKeyShare,
+    ) -> Result<DecryptionShare> {
+        let record = self.get(record_id)?;
+        DIBTDEncryption::share_decrypt_labeled(
+            &record.encapsulated_key,
+            &record.associated_data()?,
+            private_share,
+        )
+    }
+
+    /// Combine collected decryption shares and decrypt a stored record
+    pub fn decrypt_record(&self, record_id: &str, shares: &[DecryptionShare]) -> Result<Vec<u8>> {
+        let record = self.get(record_id)?;
+        let aad = record.associated_data()?;
+
+        EnvelopeEncryption::open(
+            &record.encapsulated_key,
+            &record.payload,
+            &aad,
+            shares,
+            record.metadata.group.threshold,
+        )
+    }
+
+    /// Decrypt a break-glass record with the emergency group's approvals
+    ///
+    /// `emergency_group` is the registered emergency group the approvals
+    /// come from; see `BreakGlassAccess::verify_approvals`. The signed justification entry is appended to the record's audit log
+    /// before any decryption takes place, so every break-glass opening leaves
+    /// a trail even if the caller discards the returned entry.
+    pub fn break_glass_decrypt(
+        &self,
+        record_id: &str,
+        emergency_group: &GroupIdentity,
+        justification: &Justification,
+        approvals: &[BreakGlassApproval],
+        verification_keys: &HashMap<usize, Point>,
+    ) -> Result<(Vec<u8>, JustificationEntry)> {
+        let record = self.get(record_id)?;
+        let entry = BreakGlassAccess::verify_approvals(
+            &record,
+            emergency_group,
+            justification,
+            approvals,
+            verification_keys,
+        )?;
+        self.append_audit_entry(&entry)?;
+
+        let (_, emergency_key) = BreakGlassAccess::emergency_envelope(&record)?;
+        let shares: Vec<DecryptionShare> = approvals.iter().map(|a| a.share.clone()).collect();
+        let aad = record.associated_data()?;
+        let data = EnvelopeEncryption::open(
+            emergency_key,
+            &record.payload,
+            &aad,
+            &shares,
+            emergency_group.threshold,
+        )?;
+
+        Ok((data, entry))
+    }
+
+    /// All break-glass justification entries recorded for a record
+    pub fn audit_log(&self, record_id: &str) -> Result<Vec<JustificationEntry>> {
+        let path = self.audit_path(record_id)?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        fs::read_to_string(&path)
+            .map_err(storage_error)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|e| DIBTDError::SerializationError(e.to_string()))
+            })
+            .collect()
+    }
+
+    fn append_audit_entry(&self, entry: &JustificationEntry) -> Result<()> {
+        let mut line =
+            serde_json::to_vec(entry).map_err(|e| DIBTDError::SerializationError(e.to_string()))?;
+        line.push(b'\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.audit_path(&entry.record_id)?)
+            .map_err(storage_error)?;
+        file.write_all(&line).map_err(storage_error)?;
+        file.sync_all().map_err(storage_error)
+    }
+
+    fn audit_path(&self, record_id: &str) -> Result<PathBuf> {
+        // Validates the id the same way as for the record itself
+        self.record_path(record_id)?;
+        Ok(self.root.join("audit").join(format!("{}.jsonl", record_id)))
+    }
+
+    fn records_dir(&self) -> PathBuf {
+        self.root.join("records")
+    }
+
+    fn record_path(&self, record_id: &str) -> Result<PathBuf> {
+        // Record ids are generated as hex; rejecting anything else keeps ids
+        // from escaping the records directory.
+        if record_id.is_empty() || !record_id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(DIBTDError::RecordNotFound(record_id.to_string()));
+        }
+        Ok(self.records_dir().join(format!("{}.json", record_id)))
+    }
+
+    fn read_record(path: &Path) -> Result<EncryptedRecord> {
+        let json = fs::read(path).map_err(storage_error)?;
+        serde_json::from_slice(&json).map_err(|e| DIBTDError::SerializationError(e.to_string()))
+    }
+
+    fn index(&mut self, record: &EncryptedRecord) {
+        let ids = self
+            .by_patient
+            .entry(record.metadata.patient_pseudonym.clone())
+            .or_default();
+        if !ids.contains(&record.record_id) {
+            ids.push(record.record_id.clone());
+        }
+
+        let ids = self
+            .by_group
+            .entry(record.metadata.group.id.clone())
+            .or_default();
+        if !ids.contains(&record.record_id) {
+            ids.push(record.record_id.clone());
+        }
+    }
+
+    fn load_all(&self, ids: Option<&Vec<String>>) -> Result<Vec<EncryptedRecord>> {
+        let mut records = ids
+            .into_iter()
+            .flatten()
+            .map(|id| self.get(id))
+            .collect::<Result<Vec<_>>>()?;
+        records.sort_by(|a, b| {
+            (a.metadata.created_at, &a.record_id).cmp(&(b.metadata.created_at, &b.record_id))
+        });
+        Ok(records)
+    }
+}
+
+impl EncryptedRecord {
+    /// Associated data of the record's payload, also the label of its
+    /// encapsulated keys
+    ///
+    /// Decryption shares must be generated under this label, so an
+    /// encapsulated key copied into a record with a different id, header or
+    /// metadata is rejected.
+    pub fn associated_data(&self) -> Result<Vec<u8>> {
+        record_aad(&self.record_id, &self.header, &self.metadata)
+    }
+}
+
+/// Associated data binding a record's payload to its id, envelope header and
+/// metadata
+fn record_aad(
+    record_id: &str,
+    header: &EnvelopeHeader,
+    metadata: &RecordMetadata,
+) -> Result<Vec<u8>> {
+    let encode = |e: serde_json::Error| DIBTDError::SerializationError(e.to_string());
+    let mut aad = record_id.as_bytes().to_vec();
+    aad.extend_from_slice(&serde_json::to_vec(header).map_err(encode)?);
+    aad.extend_from_slice(&serde_json::to_vec(metadata).map_err(encode)?);
+    Ok(aad)
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn storage_error(e: std::io::Error) -> DIBTDError {
+    DIBTDError::StorageError(e.to_string())
+}
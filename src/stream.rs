@@ -0,0 +1,410 @@
+use crate::{
+    aead::{constant_time_eq, key_commitment, AeadAlgorithm, Dem, COMMITMENT_LEN},
+    errors::{DIBTDError, Result},
+};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use std::io::{self, Read, Write};
+
+/// Plaintext bytes per chunk unless the caller picks another size
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Largest chunk a decryptor will buffer, bounding memory for hostile headers
+pub const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+const STREAM_VERSION: u8 = 1;
+const TAG_LEN: usize = 16;
+
+/// Nonce bytes taken by the chunk counter and the last-chunk flag
+const COUNTER_LEN: usize = 5;
+
+/// Length of the random nonce prefix for `algorithm`: 7 bytes, or 19 for XChaCha20
+pub fn prefix_len(algorithm: AeadAlgorithm) -> usize {
+    algorithm.nonce_len() - COUNTER_LEN
+}
+
+/// Length of the header written in front of the encrypted chunks:
+/// version ‖ algorithm id ‖ nonce prefix ‖ chunk size ‖ key commitment
+pub fn header_len(algorithm: AeadAlgorithm) -> usize {
+    2 + prefix_len(algorithm) + 4 + COMMITMENT_LEN
+}
+
+/// Chunk nonce: random prefix ‖ 32-bit big-endian counter ‖ last-chunk flag
+///
+/// This is the STREAM construction of Hoang, Reyhanitabar, Rogaway and Vizár:
+/// reordering, dropping or truncating chunks changes the nonce a chunk is
+/// opened under, so the tag check fails.
+fn chunk_nonce(prefix: &[u8], counter: u32, last: bool) -> Vec<u8> {
+    let mut nonce = Vec::with_capacity(prefix.len() + COUNTER_LEN);
+    nonce.extend_from_slice(prefix);
+    nonce.extend_from_slice(&counter.to_be_bytes());
+    nonce.push(last as u8);
+    nonce
+}
+
+fn to_io_error(err: DIBTDError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Encrypts everything written to it in fixed-size chunks
+///
+/// Call `finish` once all data is written: it seals the final chunk, without
+/// which the stream will not decrypt.
+pub struct StreamEncryptor<W: Write> {
+    inner: W,
+    cipher: Dem,
+    prefix: Vec<u8>,
+    aad: Vec<u8>,
+    counter: u32,
+    chunk_size: usize,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> StreamEncryptor<W> {
+    /// Start an AES-256-GCM stream with the default chunk size
+    pub fn new(key: &[u8; 32], associated_data: &[u8], inner: W) -> Result<Self> {
+        Self::new_with_rng(
+            AeadAlgorithm::default(),
+            key,
+            associated_data,
+            DEFAULT_CHUNK_SIZE,
+            inner,
+            &mut OsRng,
+        )
+    }
+
+    /// Start a stream with a caller-chosen chunk size
+    pub fn with_chunk_size(
+        key: &[u8; 32],
+        associated_data: &[u8],
+        chunk_size: usize,
+        inner: W,
+    ) -> Result<Self> {
+        Self::new_with_rng(
+            AeadAlgorithm::default(),
+            key,
+            associated_data,
+            chunk_size,
+            inner,
+            &mut OsRng,
+        )
+    }
+
+    /// Start a stream with the chosen algorithm, drawing the nonce prefix from
+    /// a caller-supplied RNG
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        algorithm: AeadAlgorithm,
+        key: &[u8; 32],
+        associated_data: &[u8],
+        chunk_size: usize,
+        inner: W,
+        rng: &mut R,
+    ) -> Result<Self> {
+        let mut prefix = vec![0u8; prefix_len(algorithm)];
+        rng.fill_bytes(&mut prefix);
+        Self::with_prefix(algorithm, key, associated_data, chunk_size, prefix, inner)
+    }
+
+    /// Start a stream whose nonce prefix is derived elsewhere, e.g. from an envelope seed
+    pub(crate) fn with_prefix(
+        algorithm: AeadAlgorithm,
+        key: &[u8; 32],
+        associated_data: &[u8],
+        chunk_size: usize,
+        prefix: Vec<u8>,
+        mut inner: W,
+    ) -> Result<Self> {
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(DIBTDError::AEADError(format!(
+                "Invalid chunk size {}",
+                chunk_size
+            )));
+        }
+        if prefix.len() != prefix_len(algorithm) {
+            return Err(DIBTDError::AEADError(
+                "Invalid nonce prefix length".to_string(),
+            ));
+        }
+
+        let commitment = key_commitment(algorithm, key, &prefix);
+        let header = encode_header(algorithm, &prefix, chunk_size, &commitment);
+        inner
+            .write_all(&header)
+            .map_err(|e| DIBTDError::AEADError(e.to_string()))?;
+
+        Ok(StreamEncryptor {
+            inner,
+            cipher: Dem::new(algorithm, key),
+            prefix,
+            aad: chunk_aad(&header, associated_data),
+            counter: 0,
+            chunk_size,
+            buffer: Vec::with_capacity(chunk_size + TAG_LEN),
+        })
+    }
+
+    /// Seal the final chunk and return the underlying writer
+    pub fn finish(self) -> Result<W> {
+        self.finish_with_tag().map(|(inner, _)| inner)
+    }
+
+    /// Seal the final chunk; also returns its tag, which commits to the whole stream
+    pub(crate) fn finish_with_tag(mut self) -> Result<(W, [u8; TAG_LEN])> {
+        let tag = self.seal_chunk(true)?;
+        self.inner
+            .flush()
+            .map_err(|e| DIBTDError::AEADError(e.to_string()))?;
+        Ok((self.inner, tag))
+    }
+
+    /// The random nonce prefix recorded in the stream header
+    pub fn nonce_prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    fn seal_chunk(&mut self, last: bool) -> Result<[u8; TAG_LEN]> {
+        let nonce = chunk_nonce(&self.prefix, self.counter, last);
+        self.cipher
+            .encrypt_in_place(&nonce, &self.aad, &mut self.buffer)?;
+
+        self.inner
+            .write_all(&self.buffer)
+            .map_err(|e| DIBTDError::AEADError(e.to_string()))?;
+
+        let mut tag = [0u8; TAG_LEN];
+        tag.copy_from_slice(&self.buffer[self.buffer.len() - TAG_LEN..]);
+        self.buffer.clear();
+
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| DIBTDError::AEADError("Stream exceeds 2^32 chunks".to_string()))?;
+        Ok(tag)
+    }
+}
+
+impl<W: Write> Write for StreamEncryptor<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        // A full chunk is only sealed once more data arrives, so the last
+        // chunk is always the one sealed by `finish`
+        if self.buffer.len() == self.chunk_size && !data.is_empty() {
+            self.seal_chunk(false).map_err(to_io_error)?;
+        }
+
+        let take = data.len().min(self.chunk_size - self.buffer.len());
+        self.buffer.extend_from_slice(&data[..take]);
+        Ok(take)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decrypts a stream produced by `StreamEncryptor`
+///
+/// Plaintext is released one authenticated chunk at a time. A stream that is
+/// truncated, reordered or tampered with yields an `InvalidData` error, but
+/// chunks read before the error were authentic. Once a chunk fails, every
+/// later `read` fails too.
+pub struct StreamDecryptor<R: Read> {
+    inner: R,
+    algorithm: AeadAlgorithm,
+    cipher: Dem,
+    prefix: Vec<u8>,
+    aad: Vec<u8>,
+    counter: u32,
+    chunk_size: usize,
+    /// Next encrypted chunk, read ahead to tell whether the current one is last
+    next: Vec<u8>,
+    plaintext: Vec<u8>,
+    position: usize,
+    final_tag: Option<[u8; TAG_LEN]>,
+    /// Set once a chunk fails; the stream cannot be resumed after that
+    failed: Option<String>,
+}
+
+impl<R: Read> StreamDecryptor<R> {
+    /// Read the stream header and prepare to decrypt
+    ///
+    /// The algorithm is taken from the header. The header's key commitment is
+    /// checked against `key` before any chunk is read, so a stream opens
+    /// under one key only.
+    pub fn new(key: &[u8; 32], associated_data: &[u8], mut inner: R) -> Result<Self> {
+        let mut header = vec![0u8; 2];
+        inner
+            .read_exact(&mut header)
+            .map_err(|e| DIBTDError::AEADError(e.to_string()))?;
+        if header[0] != STREAM_VERSION {
+            return Err(DIBTDError::AEADError(format!(
+                "Unsupported stream version {}",
+                header[0]
+            )));
+        }
+        let algorithm = AeadAlgorithm::from_id(header[1])?;
+
+        header.resize(header_len(algorithm), 0);
+        inner
+            .read_exact(&mut header[2..])
+            .map_err(|e| DIBTDError::AEADError(e.to_string()))?;
+        let (prefix, chunk_size, commitment) = decode_header(algorithm, &header)?;
+        if !constant_time_eq(&commitment, &key_commitment(algorithm, key, &prefix)) {
+            return Err(DIBTDError::AEADError("Key commitment mismatch".to_string()));
+        }
+
+        let mut decryptor = StreamDecryptor {
+            inner,
+            algorithm,
+            cipher: Dem::new(algorithm, key),
+            prefix,
+            aad: chunk_aad(&header, associated_data),
+            counter: 0,
+            chunk_size,
+            next: Vec::with_capacity(chunk_size + TAG_LEN),
+            plaintext: Vec::with_capacity(chunk_size + TAG_LEN),
+            position: 0,
+            final_tag: None,
+            failed: None,
+        };
+        decryptor.read_ahead()?;
+        Ok(decryptor)
+    }
+
+    /// The algorithm recorded in the stream header
+    pub fn algorithm(&self) -> AeadAlgorithm {
+        self.algorithm
+    }
+
+    /// The random nonce prefix recorded in the stream header
+    pub fn nonce_prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    /// Tag of the final chunk, once the whole stream has been read
+    pub(crate) fn final_tag(&self) -> Option<[u8; TAG_LEN]> {
+        self.final_tag
+    }
+
+    /// Fill `next` with up to one encrypted chunk
+    fn read_ahead(&mut self) -> Result<()> {
+        self.next.resize(self.chunk_size + TAG_LEN, 0);
+        let mut filled = 0;
+        while filled < self.next.len() {
+            match self.inner.read(&mut self.next[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(DIBTDError::AEADError(e.to_string())),
+            }
+        }
+        self.next.truncate(filled);
+        Ok(())
+    }
+
+    /// Decrypt the next chunk, leaving nothing readable if that fails
+    fn open_chunk(&mut self) -> Result<()> {
+        let result = self.decrypt_chunk();
+        if let Err(e) = &result {
+            self.plaintext.clear();
+            self.position = 0;
+            self.failed = Some(e.to_string());
+        }
+        result
+    }
+
+    fn decrypt_chunk(&mut self) -> Result<()> {
+        std::mem::swap(&mut self.plaintext, &mut self.next);
+        self.position = 0;
+
+        // A short chunk must be the last; a full one is last only at EOF
+        let last = if self.plaintext.len() < self.chunk_size + TAG_LEN {
+            true
+        } else {
+            self.read_ahead()?;
+            self.next.is_empty()
+        };
+        if self.plaintext.len() < TAG_LEN {
+            return Err(DIBTDError::AEADError("Truncated stream".to_string()));
+        }
+
+        let mut tag = [0u8; TAG_LEN];
+        tag.copy_from_slice(&self.plaintext[self.plaintext.len() - TAG_LEN..]);
+
+        let nonce = chunk_nonce(&self.prefix, self.counter, last);
+        self.cipher
+            .decrypt_in_place(&nonce, &self.aad, &mut self.plaintext)?;
+
+        if last {
+            self.final_tag = Some(tag);
+        } else {
+            self.counter = self
+                .counter
+                .checked_add(1)
+                .ok_or_else(|| DIBTDError::AEADError("Stream exceeds 2^32 chunks".to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for StreamDecryptor<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if let Some(message) = &self.failed {
+            return Err(to_io_error(DIBTDError::AEADError(message.clone())));
+        }
+        while self.position == self.plaintext.len() {
+            if self.final_tag.is_some() {
+                return Ok(0);
+            }
+            self.open_chunk().map_err(to_io_error)?;
+        }
+
+        let n = out.len().min(self.plaintext.len() - self.position);
+        out[..n].copy_from_slice(&self.plaintext[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+fn encode_header(
+    algorithm: AeadAlgorithm,
+    prefix: &[u8],
+    chunk_size: usize,
+    commitment: &[u8; COMMITMENT_LEN],
+) -> Vec<u8> {
+    let mut header = Vec::with_capacity(header_len(algorithm));
+    header.push(STREAM_VERSION);
+    header.push(algorithm.id());
+    header.extend_from_slice(prefix);
+    header.extend_from_slice(&(chunk_size as u32).to_be_bytes());
+    header.extend_from_slice(commitment);
+    header
+}
+
+fn decode_header(
+    algorithm: AeadAlgorithm,
+    header: &[u8],
+) -> Result<(Vec<u8>, usize, [u8; COMMITMENT_LEN])> {
+    let prefix_end = 2 + prefix_len(algorithm);
+    let prefix = header[2..prefix_end].to_vec();
+
+    let mut size_bytes = [0u8; 4];
+    size_bytes.copy_from_slice(&header[prefix_end..prefix_end + 4]);
+    let chunk_size = u32::from_be_bytes(size_bytes) as usize;
+    if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+        return Err(DIBTDError::AEADError(format!(
+            "Invalid chunk size {}",
+            chunk_size
+        )));
+    }
+
+    let mut commitment = [0u8; COMMITMENT_LEN];
+    commitment.copy_from_slice(&header[prefix_end + 4..]);
+
+    Ok((prefix, chunk_size, commitment))
+}
+
+/// Every chunk authenticates the header as well as the caller's associated data
+fn chunk_aad(header: &[u8], associated_data: &[u8]) -> Vec<u8> {
+    let mut aad = header.to_vec();
+    aad.extend_from_slice(associated_data);
+    aad
+}
@@ -1,11 +1,140 @@
 use crate::{
-    errors::{DIBTDError, Result},
+    errors::{DIBTDError, Result, Stage},
+    group::{Curve, Group, Point, Scalar},
+    msm::multi_scalar_mul,
     types::*,
-    utils::{lagrange_coefficient, scalar_add, scalar_mul},
+    utils::{batch_inverse, scalar_add, scalar_from_index, scalar_mul, scalar_negate},
 };
-use secp256k1::{PublicKey, Scalar, Secp256k1};
 use std::collections::HashMap;
 
+/// A fixed set of member indices with their Lagrange coefficients at zero
+///
+/// Building a quorum costs one modular inversion for the whole set; after
+/// that it can combine shares for any number of ciphertexts without
+/// recomputing coefficients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quorum {
+    indices: Vec<usize>,
+    coefficients: Vec<Scalar>,
+}
+
+impl Quorum {
+    /// Precompute λ_i = Π_{k≠i} k / (k - i) for every index in the set
+    ///
+    /// An empty set fails with `InsufficientShares(0, 1)`: any threshold
+    /// needs at least one share.
+    pub fn new(indices: &[usize]) -> Result<Self> {
+        if indices.is_empty() {
+            return Err(DIBTDError::InsufficientShares(0, 1));
+        }
+        for (position, index) in indices.iter().enumerate() {
+            if indices[..position].contains(index) {
+                return Err(DIBTDError::DuplicateIndex(*index));
+            }
+        }
+        
+        let points: Vec<Scalar> = indices.iter().map(|&k| scalar_from_index(k)).collect();
+        
+        // Numerators Π_{k≠i} k from prefix and suffix products
+        let mut numerators = vec![Curve::SCALAR_ONE; points.len()];
+        let mut product = Curve::SCALAR_ONE;
+        for (numerator, point) in numerators.iter_mut().zip(&points) {
+            *numerator = product;
+            product = scalar_mul(&product, point);
+        }
+        product = Curve::SCALAR_ONE;
+        for (numerator, point) in numerators.iter_mut().zip(&points).rev() {
+            *numerator = scalar_mul(numerator, &product);
+            product = scalar_mul(&product, point);
+        }
+        
+        // Denominators Π_{k≠i} (k - i), inverted together
+        let denominators: Vec<Scalar> = points
+            .iter()
+            .enumerate()
+            .map(|(i, x_i)| {
+                let minus_x_i = scalar_negate(x_i);
+                points
+                    .iter()
+                    .enumerate()
+                    .filter(|&(k, _)| k != i)
+                    .fold(Curve::SCALAR_ONE, |acc, (_, x_k)| scalar_mul(&acc, &scalar_add(x_k, &minus_x_i)))
+            })
+            .collect();
+        // Only non-invertible if two indices are congruent modulo q
+        let inverses = batch_inverse(&denominators)
+            .ok_or(DIBTDError::DuplicateIndex(indices[0]))?;
+        
+        let coefficients = numerators
+            .iter()
+            .zip(&inverses)
+            .map(|(numerator, inverse)| scalar_mul(numerator, inverse))
+            .collect();
+        
+        Ok(Quorum {
+            indices: indices.to_vec(),
+            coefficients,
+        })
+    }
+    
+    /// Member indices in the order they were given
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+    
+    /// Number of members, i.e. the threshold the quorum reconstructs at
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+    
+    /// Lagrange coefficient of `index`, if it is a member
+    pub fn coefficient(&self, index: usize) -> Option<Scalar> {
+        self.indices
+            .iter()
+            .position(|&i| i == index)
+            .map(|position| self.coefficients[position])
+    }
+    
+    /// Interpolate a secret from scalar shares of exactly this quorum
+    pub fn interpolate(&self, shares: &[(usize, Scalar)]) -> Result<Scalar> {
+        Ok(self
+            .weigh(shares)?
+            .iter()
+            .fold(Curve::SCALAR_ZERO, |acc, (share, coeff)| scalar_add(&acc, &scalar_mul(share, coeff))))
+    }
+    
+    /// Compute Σ λ_i * S_i over point shares of exactly this quorum
+    pub fn combine(&self, shares: &[(usize, Point)]) -> Result<Point> {
+        multi_scalar_mul(&self.weigh(shares)?)?
+            .ok_or(DIBTDError::DecryptionFailed { stage: Stage::Combination })
+    }
+    
+    /// Pair each member's share with its coefficient
+    ///
+    /// Shares from non-members are ignored; every member must appear once.
+    fn weigh<T: Copy>(&self, shares: &[(usize, T)]) -> Result<Vec<(T, Scalar)>> {
+        let mut weighted: Vec<Option<(T, Scalar)>> = vec![None; self.indices.len()];
+        for &(index, share) in shares {
+            if let Some(position) = self.indices.iter().position(|&i| i == index) {
+                if weighted[position].is_some() {
+                    return Err(DIBTDError::DuplicateIndex(index));
+                }
+                weighted[position] = Some((share, self.coefficients[position]));
+            }
+        }
+        
+        let found = weighted.iter().filter(|w| w.is_some()).count();
+        if found < self.indices.len() {
+            return Err(DIBTDError::InsufficientShares(found, self.indices.len()));
+        }
+        Ok(weighted.into_iter().flatten().collect())
+    }
+}
+
 pub struct ThresholdOperations;
 
 impl ThresholdOperations {
@@ -18,42 +147,21 @@ impl ThresholdOperations {
             return Err(DIBTDError::InsufficientShares(shares.len(), threshold));
         }
         
-        let indices: Vec<usize> = shares.iter().map(|(i, _)| *i).collect();
-        let mut result = Scalar::ZERO;
-        
-        for &(index, ref share) in shares.iter().take(threshold) {
-            let coeff = lagrange_coefficient(&indices[..threshold], index, 0)?;
-            let weighted = scalar_mul(share, &coeff);
-            result = scalar_add(&result, &weighted);
-        }
-        
-        Ok(result)
+        let indices: Vec<usize> = shares.iter().take(threshold).map(|(i, _)| *i).collect();
+        Quorum::new(&indices)?.interpolate(&shares[..threshold])
     }
     
     /// Reconstruct a public key from shares
     pub fn reconstruct_public_key(
-        shares: &[(usize, PublicKey)],
+        shares: &[(usize, Point)],
         threshold: usize,
-    ) -> Result<PublicKey> {
+    ) -> Result<Point> {
         if shares.len() < threshold {
             return Err(DIBTDError::InsufficientShares(shares.len(), threshold));
         }
         
-        let secp = Secp256k1::new();
-        let indices: Vec<usize> = shares.iter().map(|(i, _)| *i).collect();
-        let mut result: Option<PublicKey> = None;
-        
-        for &(index, ref share) in shares.iter().take(threshold) {
-            let coeff = lagrange_coefficient(&indices[..threshold], index, 0)?;
-            let weighted = share.mul_tweak(&secp, &coeff)?;
-            
-            result = Some(match result {
-                None => weighted,
-                Some(acc) => acc.combine(&weighted)?,
-            });
-        }
-        
-        result.ok_or(DIBTDError::DecryptionFailed)
+        let indices: Vec<usize> = shares.iter().take(threshold).map(|(i, _)| *i).collect();
+        Quorum::new(&indices)?.combine(&shares[..threshold])
     }
     
     /// Verify threshold consistency for a set of shares
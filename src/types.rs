@@ -1,40 +1,87 @@
-use secp256k1::{PublicKey, Scalar};
+use crate::aead::{AeadAlgorithm, COMMITMENT_LEN};
+use crate::ciphersuite::Ciphersuite;
+use crate::group::{Curve, Group, Point, Scalar};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use rand::rngs::OsRng;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
 
-// Custom serialization for Scalar
+// Custom serialization for Scalar: big-endian bytes, hex in human-readable formats
 mod scalar_serde {
     use super::*;
+    use crate::utils::{from_hex, to_hex};
     use serde::{Deserializer, Serializer};
     
     pub fn serialize<S>(scalar: &Scalar, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let bytes = scalar.to_be_bytes();
-        serializer.serialize_bytes(&bytes)
+        let bytes = Curve::scalar_to_bytes(scalar);
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&to_hex(&bytes))
+        } else {
+            serializer.serialize_bytes(&bytes)
+        }
     }
     
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Scalar, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let bytes: Vec<u8> = Deserialize::deserialize(deserializer)?;
+        let bytes: Vec<u8> = if deserializer.is_human_readable() {
+            let hex: String = Deserialize::deserialize(deserializer)?;
+            from_hex(&hex).map_err(serde::de::Error::custom)?
+        } else {
+            Deserialize::deserialize(deserializer)?
+        };
         if bytes.len() != 32 {
             return Err(serde::de::Error::custom("Invalid scalar bytes length"));
         }
         let mut arr = [0u8; 32];
         arr.copy_from_slice(&bytes);
-        Scalar::from_be_bytes(arr)
+        Curve::scalar_from_bytes(&arr)
             .map_err(|_| serde::de::Error::custom("Invalid scalar value"))
     }
 }
 
+// Custom serialization for Point: compressed encoding, hex in human-readable formats
+mod point_serde {
+    use super::*;
+    use crate::utils::{from_hex, to_hex};
+    use serde::{Deserializer, Serializer};
+    
+    pub fn serialize<S>(point: &Point, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let bytes = Curve::point_to_bytes(point);
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&to_hex(&bytes))
+        } else {
+            serializer.serialize_bytes(&bytes)
+        }
+    }
+    
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Point, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = if deserializer.is_human_readable() {
+            let hex: String = Deserialize::deserialize(deserializer)?;
+            from_hex(&hex).map_err(serde::de::Error::custom)?
+        } else {
+            Deserialize::deserialize(deserializer)?
+        };
+        Curve::point_from_bytes(&bytes)
+            .map_err(|_| serde::de::Error::custom("Invalid point encoding"))
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MasterPublicKey {
-    pub y: PublicKey,
-    pub gamma: PublicKey,
+    #[serde(with = "point_serde")]
+    pub y: Point,
+    #[serde(with = "point_serde")]
+    pub gamma: Point,
     pub params: SystemParams,
 }
 
@@ -43,32 +90,65 @@ pub struct MasterSecretShare {
     pub index: usize,
     pub s_i: Scalar,
     pub z_i: Scalar,
+    pub ciphersuite: Ciphersuite,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SystemParams {
     pub n: usize,  // Total participants
     pub t: usize,  // Threshold
+    pub ciphersuite: Ciphersuite,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Ciphertext {
-    pub d: PublicKey,
-    pub e: PublicKey,
+    #[serde(with = "point_serde")]
+    pub d: Point,
+    #[serde(with = "point_serde")]
+    pub e: Point,
     pub f: Vec<u8>,
     #[serde(with = "scalar_serde")]
     pub delta: Scalar,
+    /// Suite of the key it was encrypted under; the AEAD part names the
+    /// payload algorithm when the ciphertext encapsulates an envelope key
+    ///
+    /// Required when deserializing, so a ciphertext without a suite is
+    /// refused rather than assumed to be of this build's.
+    pub ciphersuite: Ciphersuite,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DecryptionShare {
     pub index: usize,
-    pub lambda_i: PublicKey,
+    #[serde(with = "point_serde")]
+    pub lambda_i: Point,
+    /// Proof that Λ_i uses the member's key share; see `ShareProof`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<ShareProof>,
+    /// Suite of the key share that produced it
+    pub ciphersuite: Ciphersuite,
 }
 
-#[derive(Clone, Debug)]
+/// Chaum-Pedersen proof that log_P(VK_i) = log_D(Λ_i)
+///
+/// Carries the commitments A_1 = k * P and A_2 = k * D rather than the
+/// challenge, so many proofs can be checked with one multi-scalar
+/// multiplication (see `ZKProof::batch_verify_decryption_shares`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShareProof {
+    #[serde(with = "point_serde")]
+    pub a_1: Point,
+    #[serde(with = "point_serde")]
+    pub a_2: Point,
+    #[serde(with = "scalar_serde")]
+    pub response: Scalar,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Proof {
-    pub r: PublicKey,
+    #[serde(with = "point_serde")]
+    pub r: Point,
+    #[serde(with = "scalar_serde")]
     pub mu: Scalar,
 }
 
@@ -76,7 +156,8 @@ pub struct Proof {
 pub struct PrivateKeyShare {
     pub index: usize,
     pub psi_i: Scalar,
-    pub verification_key: PublicKey,
+    pub verification_key: Point,
+    pub ciphersuite: Ciphersuite,
 }
 
 #[derive(Clone, Debug)]
@@ -86,18 +167,32 @@ pub struct Polynomial {
 
 impl Polynomial {
     pub fn new(degree: usize) -> Self {
+        Self::new_with_rng(degree, &mut OsRng)
+    }
+    
+    /// Random polynomial of the given degree drawn from a caller-supplied RNG
+    pub fn new_with_rng<R: RngCore + CryptoRng>(degree: usize, rng: &mut R) -> Self {
         let mut coefficients = Vec::with_capacity(degree + 1);
         for _ in 0..=degree {
-            coefficients.push(Scalar::random_custom(&mut OsRng));
+            coefficients.push(Curve::scalar_random(&mut *rng));
         }
         Self { coefficients }
     }
     
     pub fn with_constant(degree: usize, constant: Scalar) -> Self {
+        Self::with_constant_with_rng(degree, constant, &mut OsRng)
+    }
+    
+    /// Random polynomial with a fixed constant term drawn from a caller-supplied RNG
+    pub fn with_constant_with_rng<R: RngCore + CryptoRng>(
+        degree: usize,
+        constant: Scalar,
+        rng: &mut R,
+    ) -> Self {
         let mut coefficients = Vec::with_capacity(degree + 1);
         coefficients.push(constant);
         for _ in 1..=degree {
-            coefficients.push(Scalar::random_custom(&mut OsRng));
+            coefficients.push(Curve::scalar_random(&mut *rng));
         }
         Self { coefficients }
     }
@@ -105,8 +200,8 @@ impl Polynomial {
     pub fn evaluate(&self, x: usize) -> Scalar {
         use crate::utils::{scalar_add, scalar_mul, scalar_from_u32};
         
-        let mut result = Scalar::ZERO;
-        let mut x_power = Scalar::ONE;
+        let mut result = Curve::SCALAR_ZERO;
+        let mut x_power = Curve::SCALAR_ONE;
         let x_scalar = scalar_from_u32(x as u32);
         
         for coeff in &self.coefficients {
@@ -123,22 +218,152 @@ pub struct DKGParticipant {
     pub index: usize,
     pub f_0: Polynomial,
     pub f_1: Polynomial,
-    pub commitments_0: Vec<PublicKey>,
-    pub commitments_1: Vec<PublicKey>,
+    pub commitments_0: Vec<Point>,
+    pub commitments_1: Vec<Point>,
     pub shares_received: HashMap<usize, (Scalar, Scalar)>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GroupIdentity {
     pub id: String,
     pub threshold: usize,
     pub members: usize,
 }
 
-#[derive(Clone, Debug)]
+/// Current `AEADPacket` format version
+pub const AEAD_PACKET_VERSION: u8 = 1;
+
+/// A self-describing, key-committing AEAD ciphertext
+///
+/// Produced by `AEADCipher::encrypt_packet`; carries everything but the key
+/// needed to open it, so the tag never has to be sliced off by hand.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AEADPacket {
+    pub version: u8,
+    pub algorithm: AeadAlgorithm,
+    pub nonce: Vec<u8>,
+    /// Commitment to key and nonce, checked before decryption
+    pub commitment: [u8; COMMITMENT_LEN],
     pub ciphertext: Vec<u8>,
-    pub nonce: [u8; 12],
     pub tag: [u8; 16],
     pub associated_data: Vec<u8>,
-}
\ No newline at end of file
+}
+
+/// An `AEADPacket` together with the DIBTD encapsulation of its key seed
+///
+/// The packet's associated data is the DIBTD label, so decryption shares
+/// come from `DIBTDEncryption::share_decrypt_labeled` over
+/// `packet.associated_data`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Envelope {
+    pub encapsulated_key: Ciphertext,
+    pub packet: AEADPacket,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordMetadata {
+    pub patient_pseudonym: String,
+    pub record_type: String,
+    pub group: GroupIdentity,
+    pub created_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EnvelopeMode {
+    #[default]
+    Standard,
+    BreakGlass,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeHeader {
+    pub mode: EnvelopeMode,
+    pub emergency_group: Option<GroupIdentity>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncryptedRecord {
+    pub record_id: String,
+    #[serde(default)]
+    pub header: EnvelopeHeader,
+    pub metadata: RecordMetadata,
+    pub stored_at: u64,
+    pub encapsulated_key: Ciphertext,
+    #[serde(default)]
+    pub emergency_key: Option<Ciphertext>,
+    pub payload: AEADPacket,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FieldRule {
+    pub path: String,
+    pub group: GroupIdentity,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FieldPolicy {
+    pub rules: Vec<FieldRule>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncryptedField {
+    pub group: GroupIdentity,
+    pub encapsulated_key: Ciphertext,
+    pub payload: AEADPacket,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Justification {
+    pub requested_by: String,
+    pub reason: String,
+    pub requested_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BreakGlassApproval {
+    pub share: DecryptionShare,
+    pub signature: Proof,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JustificationEntry {
+    pub record_id: String,
+    pub patient_pseudonym: String,
+    pub emergency_group: String,
+    pub justification: Justification,
+    pub signatures: Vec<(usize, Proof)>,
+    pub opened_at: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConsentScope {
+    AllRecords,
+    Records(Vec<ConsentedRecord>),
+}
+
+/// A record covered by a consent grant, pinned to its encapsulated key
+///
+/// Built with `PatientConsent::consented_record`, so the grant only covers
+/// the key the patient saw and not one swapped into the record later.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsentedRecord {
+    pub record_id: String,
+    /// Hex SHA-256 digest of the record's encapsulated key
+    pub key_digest: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsentGrant {
+    pub patient_pseudonym: String,
+    pub group_id: String,
+    pub scope: ConsentScope,
+    pub purpose: String,
+    pub issued_at: u64,
+    pub expires_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedConsent {
+    pub grant: ConsentGrant,
+    pub signature: secp256k1::ecdsa::Signature,
+}
@@ -1,138 +1,126 @@
-use secp256k1::{Scalar, PublicKey, SecretKey, Secp256k1};
-use sha2::{Sha256, Digest};
-use crate::errors::Result;
+use crate::ciphersuite::Ciphersuite;
+use crate::errors::{DIBTDError, Result};
+use crate::group::{Curve, Group, Point, Scalar};
+use crate::hash::{hash_to_bytes, hash_to_scalar, Domain};
 use crate::types::Proof;
-use num_bigint::{BigUint, BigInt};
-use num_traits::{One, Zero};
-use rand::rngs::OsRng;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
 
 /// Compute Lagrange coefficient for threshold cryptography
+///
+/// Fails with `DuplicateIndex` if the same index appears twice in `indices`.
 pub fn lagrange_coefficient(indices: &[usize], i: usize, j: usize) -> Result<Scalar> {
-    let mut num = BigUint::one();
-    let mut den = BigUint::one();
+    for (position, index) in indices.iter().enumerate() {
+        if indices[..position].contains(index) {
+            return Err(DIBTDError::DuplicateIndex(*index));
+        }
+    }
+    
+    let mut num = Curve::SCALAR_ONE;
+    let mut den = Curve::SCALAR_ONE;
     
-    let q = BigUint::from_bytes_be(&[
-        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE,
-        0xBA, 0xAE, 0xDC, 0xE6, 0xAF, 0x48, 0xA0, 0x3B,
-        0xBF, 0xD2, 0x5E, 0x8C, 0xD0, 0x36, 0x41, 0x41,
-    ]);
+    let i_scalar = scalar_from_index(i);
+    let j_scalar = scalar_from_index(j);
     
     for &k in indices {
         if k != i {
-            let k_big = BigUint::from(k);
-            let i_big = BigUint::from(i);
-            let j_big = BigUint::from(j);
+            let neg_k = scalar_negate(&scalar_from_index(k));
             
             // numerator: (j - k) mod q
-            let num_factor = if j >= k {
-                (j_big.clone() - k_big.clone()) % &q
-            } else {
-                &q - ((k_big.clone() - j_big.clone()) % &q)
-            };
-            num = (num * num_factor) % &q;
+            num = scalar_mul(&num, &scalar_add(&j_scalar, &neg_k));
             
             // denominator: (i - k) mod q
-            let den_factor = if i >= k {
-                (i_big.clone() - k_big) % &q
-            } else {
-                &q - ((k_big - i_big) % &q)
-            };
-            
-            den = (den * den_factor) % &q;
+            den = scalar_mul(&den, &scalar_add(&i_scalar, &neg_k));
         }
     }
     
-    // Compute den^(-1) mod q
-    let den_inv = mod_inverse(&den, &q)?;
-    let result = (num * den_inv) % &q;
-    
-    let bytes = result.to_bytes_be();
-    let mut scalar_bytes = [0u8; 32];
-    let offset = 32_usize.saturating_sub(bytes.len());
-    scalar_bytes[offset..].copy_from_slice(&bytes);
-    
-    Ok(Scalar::from_be_bytes(scalar_bytes).unwrap())
+    // Only non-invertible if two indices are congruent modulo q
+    let den_inv = scalar_inverse(&den).ok_or(DIBTDError::DuplicateIndex(i))?;
+    Ok(scalar_mul(&num, &den_inv))
 }
 
-fn mod_inverse(a: &BigUint, m: &BigUint) -> Result<BigUint> {
-    let a_int = BigInt::from(a.clone());
-    let m_int = BigInt::from(m.clone());
-    
-    let (gcd, x, _) = extended_gcd_signed(&a_int, &m_int);
-    
-    if gcd != BigInt::one() {
-        return Err(crate::errors::DIBTDError::InvalidThreshold(0, 0));
-    }
-    
-    // Ensure x is positive
-    let result = ((x % &m_int) + &m_int) % &m_int;
-    
-    // Convert back to BigUint (we know it's positive now)
-    Ok(result.to_biguint().unwrap())
+/// Multiplicative inverse modulo the curve order; `None` for zero
+pub fn scalar_inverse(a: &Scalar) -> Option<Scalar> {
+    Curve::scalar_invert(a)
 }
 
-fn extended_gcd_signed(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
-    if a.is_zero() {
-        return (b.clone(), BigInt::zero(), BigInt::one());
+/// Invert every element with a single modular inversion (Montgomery's trick)
+///
+/// Returns `None` if any element is zero.
+pub fn batch_inverse(values: &[Scalar]) -> Option<Vec<Scalar>> {
+    if values.is_empty() {
+        return Some(Vec::new());
     }
     
-    let (gcd, x1, y1) = extended_gcd_signed(&(b % a), a);
-    let x = y1.clone() - (b / a) * x1.clone();
-    let y = x1;
+    // prefix[i] = values[0] * ... * values[i - 1]
+    let mut prefix = Vec::with_capacity(values.len());
+    let mut product = Curve::SCALAR_ONE;
+    for value in values {
+        prefix.push(product);
+        product = scalar_mul(&product, value);
+    }
     
-    (gcd, x, y)
+    let mut inverse = scalar_inverse(&product)?;
+    let mut result = vec![Curve::SCALAR_ZERO; values.len()];
+    for i in (0..values.len()).rev() {
+        result[i] = scalar_mul(&inverse, &prefix[i]);
+        inverse = scalar_mul(&inverse, &values[i]);
+    }
+    Some(result)
 }
 
-/// Hash function H1: {0,1}* -> Z_q
-pub fn hash_h1(data: &[u8]) -> Scalar {
-    let mut hasher = Sha256::new();
-    hasher.update(b"H1:");
-    hasher.update(data);
-    let hash = hasher.finalize();
-    Scalar::from_be_bytes(hash.into()).unwrap()
+/// Hash function H1: {0,1}* -> Z_q under the suite's tag
+pub fn hash_h1(data: &[u8], suite: &Ciphersuite) -> Scalar {
+    hash_to_scalar(data, &Domain::H1.dst(suite))
 }
 
 /// Hash function H2: G -> {0,1}^ρ
-pub fn hash_h2(point: &PublicKey) -> Vec<u8> {
-    let mut hasher = Sha256::new();
-    hasher.update(b"H2:");
-    hasher.update(&point.serialize());
-    hasher.finalize().to_vec()
+pub fn hash_h2(point: &Point, suite: &Ciphersuite) -> Vec<u8> {
+    hash_h2_bytes(&Curve::point_to_bytes(point), suite)
 }
 
 /// Hash function H2 for arbitrary bytes: {0,1}* -> {0,1}^ρ
-pub fn hash_h2_bytes(data: &[u8]) -> Vec<u8> {
-    let mut hasher = Sha256::new();
-    hasher.update(b"H2:");
-    hasher.update(data);
-    hasher.finalize().to_vec()
+pub fn hash_h2_bytes(data: &[u8], suite: &Ciphersuite) -> Vec<u8> {
+    hash_to_bytes(data, &Domain::H2.dst(suite))
 }
 
-/// Hash function H3: G×G×G -> Z_q
-pub fn hash_h3(p1: &PublicKey, p2: &PublicKey, data: &[u8]) -> Scalar {
-    let mut hasher = Sha256::new();
-    hasher.update(b"H3:");
-    hasher.update(&p1.serialize());
-    hasher.update(&p2.serialize());
-    hasher.update(data);
-    let hash = hasher.finalize();
-    Scalar::from_be_bytes(hash.into()).unwrap()
+/// Hash function H3: G×G×{0,1}*×{0,1}* -> Z_q
+///
+/// `data` is length-prefixed so that no (data, label) pair collides with
+/// another one; the label takes the remainder of the input.
+pub fn hash_h3(
+    p1: &Point,
+    p2: &Point,
+    data: &[u8],
+    label: &[u8],
+    suite: &Ciphersuite,
+) -> Scalar {
+    let mut input = Curve::point_to_bytes(p1);
+    input.extend_from_slice(&Curve::point_to_bytes(p2));
+    input.extend_from_slice(&(data.len() as u64).to_be_bytes());
+    input.extend_from_slice(data);
+    input.extend_from_slice(label);
+    hash_to_scalar(&input, &Domain::H3.dst(suite))
 }
 
 /// Compute a Schnorr signature for proof of knowledge
-pub fn schnorr_prove(secret: &Scalar, context: &str) -> Result<Proof> {
-    let secp = Secp256k1::new();
-    let k = Scalar::random_custom(&mut OsRng);
+pub fn schnorr_prove(secret: &Scalar, context: &str, suite: &Ciphersuite) -> Result<Proof> {
+    schnorr_prove_with_rng(secret, context, suite, &mut OsRng)
+}
+
+/// Compute a Schnorr signature with a nonce drawn from a caller-supplied RNG
+pub fn schnorr_prove_with_rng<R: RngCore + CryptoRng>(
+    secret: &Scalar,
+    context: &str,
+    suite: &Ciphersuite,
+    rng: &mut R,
+) -> Result<Proof> {
+    let public_key = Curve::mul_generator(secret)?;
+    let k = Curve::scalar_random(rng);
     
-    // Convert k to secret key for commitment
-    let k_key = SecretKey::from_slice(&k.to_be_bytes()).unwrap();
-    let r = PublicKey::from_secret_key(&secp, &k_key);
+    // Commitment R = k*G
+    let r = Curve::mul_generator(&k)?;
     
-    let mut hasher = Sha256::new();
-    hasher.update(context.as_bytes());
-    hasher.update(&r.serialize());
-    let c = Scalar::from_be_bytes(hasher.finalize().into()).unwrap();
+    let c = schnorr_challenge(&r, &public_key, context, suite);
     
     // Scalar arithmetic: mu = k + secret * c
     let secret_c = scalar_mul(secret, &c);
@@ -141,27 +129,66 @@ pub fn schnorr_prove(secret: &Scalar, context: &str) -> Result<Proof> {
     Ok(Proof { r, mu })
 }
 
+/// Schnorr challenge c = H(R, Y, context) for the public key Y
+///
+/// R and Y have a fixed length, so the context takes the remainder of the
+/// input without a delimiter.
+pub fn schnorr_challenge(
+    r: &Point,
+    public_key: &Point,
+    context: &str,
+    suite: &Ciphersuite,
+) -> Scalar {
+    let mut input = Curve::point_to_bytes(r);
+    input.extend_from_slice(&Curve::point_to_bytes(public_key));
+    input.extend_from_slice(context.as_bytes());
+    hash_to_scalar(&input, &Domain::Schnorr.dst(suite))
+}
+
+/// Chaum-Pedersen challenge c = H(VK, D, Λ, A_1, A_2) for log_P(VK) = log_D(Λ)
+///
+/// Every input has a fixed length, so none needs a delimiter.
+pub fn share_proof_challenge(
+    verification_key: &Point,
+    d: &Point,
+    lambda_i: &Point,
+    a_1: &Point,
+    a_2: &Point,
+    suite: &Ciphersuite,
+) -> Scalar {
+    let mut input = Vec::new();
+    for point in [verification_key, d, lambda_i, a_1, a_2] {
+        input.extend_from_slice(&Curve::point_to_bytes(point));
+    }
+    hash_to_scalar(&input, &Domain::ShareProof.dst(suite))
+}
+
 /// Verify a Schnorr signature
-pub fn schnorr_verify(proof: &Proof, public_key: &PublicKey, context: &str) -> bool {
-    let secp = Secp256k1::new();
-    
-    let mut hasher = Sha256::new();
-    hasher.update(context.as_bytes());
-    hasher.update(&proof.r.serialize());
-    let c = Scalar::from_be_bytes(hasher.finalize().into()).unwrap();
+///
+/// Returns `Ok(false)` for a well-formed proof that does not verify and
+/// `InvalidProof` for one that cannot be evaluated at all (e.g. a zero `mu`).
+pub fn schnorr_verify(
+    proof: &Proof,
+    public_key: &Point,
+    context: &str,
+    suite: &Ciphersuite,
+) -> Result<bool> {
+    let c = schnorr_challenge(&proof.r, public_key, context, suite);
     
-    // Convert mu to secret key to get mu*G
-    let mu_key = SecretKey::from_slice(&proof.mu.to_be_bytes()).unwrap();
-    let mu_point = PublicKey::from_secret_key(&secp, &mu_key);
+    // mu*G
+    let mu_point = Curve::mul_generator(&proof.mu)
+        .map_err(|_| DIBTDError::InvalidProof)?;
     
     // Compute c*public_key
-    let c_pk = public_key.mul_tweak(&secp, &c).unwrap();
+    let c_pk = Curve::mul(public_key, &c)
+        .map_err(|_| DIBTDError::InvalidProof)?;
     
     // Compute r + c*public_key
-    let rhs = proof.r.combine(&c_pk).unwrap();
+    let rhs = Curve::add(&proof.r, &c_pk)
+        .map_err(|_| DIBTDError::InvalidProof)?;
     
     // Verify: mu*G = r + c*public_key
-    mu_point == rhs
+    Ok(mu_point == rhs)
 }
 
 /// XOR operation for byte arrays
@@ -169,6 +196,27 @@ pub fn xor_bytes(a: &[u8], b: &[u8]) -> Vec<u8> {
     a.iter().zip(b.iter()).map(|(x, y)| x ^ y).collect()
 }
 
+/// Encode bytes as a lowercase hex string
+pub fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode a hex string into bytes
+#[allow(clippy::manual_is_multiple_of)]
+pub fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return Err(DIBTDError::SerializationError("Odd-length hex string".to_string()));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| DIBTDError::SerializationError("Invalid hex string".to_string()))
+        })
+        .collect()
+}
+
 /// Pad or truncate bytes to specified length
 pub fn pad_or_truncate(data: &[u8], len: usize) -> Vec<u8> {
     let mut result = vec![0u8; len];
@@ -179,76 +227,43 @@ pub fn pad_or_truncate(data: &[u8], len: usize) -> Vec<u8> {
 
 // Helper functions for scalar arithmetic
 pub fn scalar_add(a: &Scalar, b: &Scalar) -> Scalar {
-    let a_bytes = a.to_be_bytes();
-    let b_bytes = b.to_be_bytes();
-    
-    // Perform addition modulo the curve order
-    let a_big = BigUint::from_bytes_be(&a_bytes);
-    let b_big = BigUint::from_bytes_be(&b_bytes);
-    let q = BigUint::from_bytes_be(&[
-        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE,
-        0xBA, 0xAE, 0xDC, 0xE6, 0xAF, 0x48, 0xA0, 0x3B,
-        0xBF, 0xD2, 0x5E, 0x8C, 0xD0, 0x36, 0x41, 0x41,
-    ]);
-    
-    let sum = (a_big + b_big) % q;
-    let bytes = sum.to_bytes_be();
-    let mut result_bytes = [0u8; 32];
-    let offset = 32_usize.saturating_sub(bytes.len());
-    result_bytes[offset..].copy_from_slice(&bytes);
-    
-    Scalar::from_be_bytes(result_bytes).unwrap()
+    Curve::scalar_add(a, b)
 }
 
 pub fn scalar_mul(a: &Scalar, b: &Scalar) -> Scalar {
-    let a_bytes = a.to_be_bytes();
-    let b_bytes = b.to_be_bytes();
-    
-    let a_big = BigUint::from_bytes_be(&a_bytes);
-    let b_big = BigUint::from_bytes_be(&b_bytes);
-    let q = BigUint::from_bytes_be(&[
-        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE,
-        0xBA, 0xAE, 0xDC, 0xE6, 0xAF, 0x48, 0xA0, 0x3B,
-        0xBF, 0xD2, 0x5E, 0x8C, 0xD0, 0x36, 0x41, 0x41,
-    ]);
-    
-    let product = (a_big * b_big) % q;
-    let bytes = product.to_bytes_be();
-    let mut result_bytes = [0u8; 32];
-    let offset = 32_usize.saturating_sub(bytes.len());
-    result_bytes[offset..].copy_from_slice(&bytes);
-    
-    Scalar::from_be_bytes(result_bytes).unwrap()
+    Curve::scalar_mul(a, b)
 }
 
 pub fn scalar_negate(a: &Scalar) -> Scalar {
-    let a_bytes = a.to_be_bytes();
-    let a_big = BigUint::from_bytes_be(&a_bytes);
-    let q = BigUint::from_bytes_be(&[
-        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE,
-        0xBA, 0xAE, 0xDC, 0xE6, 0xAF, 0x48, 0xA0, 0x3B,
-        0xBF, 0xD2, 0x5E, 0x8C, 0xD0, 0x36, 0x41, 0x41,
-    ]);
-    
-    let neg = if a_big.is_zero() {
-        BigUint::zero()
-    } else {
-        q - a_big
-    };
-    
-    let bytes = neg.to_bytes_be();
-    let mut result_bytes = [0u8; 32];
-    let offset = 32_usize.saturating_sub(bytes.len());
-    result_bytes[offset..].copy_from_slice(&bytes);
-    
-    Scalar::from_be_bytes(result_bytes).unwrap()
+    Curve::scalar_negate(a)
 }
 
 pub fn scalar_from_u32(value: u32) -> Scalar {
-    let mut bytes = [0u8; 32];
-    bytes[28..32].copy_from_slice(&value.to_be_bytes());
-    Scalar::from_be_bytes(bytes).unwrap()
-}
\ No newline at end of file
+    Curve::scalar_from_u64(value as u64)
+}
+
+pub fn scalar_from_index(index: usize) -> Scalar {
+    Curve::scalar_from_u64(index as u64)
+}
+
+/// Apply `f` to every item, across threads when the `parallel` feature is on
+///
+/// Results are returned in input order either way.
+#[cfg(feature = "parallel")]
+pub(crate) fn map_ordered<T, U, F>(items: &[T], f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync + Send,
+{
+    use rayon::prelude::*;
+    items.par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn map_ordered<T, U, F>(items: &[T], f: F) -> Vec<U>
+where
+    F: Fn(&T) -> U,
+{
+    items.iter().map(f).collect()
+}
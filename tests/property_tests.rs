@@ -0,0 +1,170 @@
+//! Property-based tests over thresholds, member subsets, message sizes and
+//! share corruptions.
+
+use dibtd_ephr::group::{Curve, Group, Point, Scalar};
+use dibtd_ephr::*;
+use proptest::prelude::*;
+use proptest::sample::subsequence;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// A single DKG setup shared by most cases; their properties are about groups
+fn system() -> &'static (MasterPublicKey, HashMap<usize, MasterSecretShare>) {
+    static SYSTEM: OnceLock<(MasterPublicKey, HashMap<usize, MasterSecretShare>)> = OnceLock::new();
+    SYSTEM.get_or_init(|| run_dkg(3, 2))
+}
+
+fn run_dkg(n: usize, t: usize) -> (MasterPublicKey, HashMap<usize, MasterSecretShare>) {
+    let mut dkg = dkg::DKGProtocol::new(n, t).unwrap();
+    for i in 1..=n {
+        dkg.init_participant(i).unwrap();
+    }
+    for from in 1..=n {
+        for (to, share) in dkg.distribute_shares(from).unwrap() {
+            dkg.receive_shares(to, from, share).unwrap();
+        }
+    }
+    dkg.finalize().unwrap()
+}
+
+/// Random DKG (threshold, nodes) with 1 <= t <= n <= 5
+fn dkg_params() -> impl Strategy<Value = (usize, usize)> {
+    (1usize..=5).prop_flat_map(|n| (1..=n, Just(n)))
+}
+
+/// Random (threshold, members) with 1 <= t <= n <= 8
+fn group_params() -> impl Strategy<Value = (usize, usize)> {
+    (1usize..=8).prop_flat_map(|n| (1..=n, Just(n)))
+}
+
+/// Group parameters together with a shuffled quorum of at least t members
+fn group_with_quorum() -> impl Strategy<Value = (usize, usize, Vec<usize>)> {
+    group_params().prop_flat_map(|(t, n)| {
+        (
+            Just(t),
+            Just(n),
+            subsequence((1..=n).collect::<Vec<_>>(), t..=n).prop_shuffle(),
+        )
+    })
+}
+
+fn encrypt_for_group(
+    t: usize,
+    n: usize,
+    message: &[u8],
+) -> (Ciphertext, HashMap<usize, PrivateKeyShare>) {
+    let (mpk, master_shares) = system();
+    let group = GroupIdentity {
+        id: format!("prop_group_{}_{}", t, n),
+        threshold: t,
+        members: n,
+    };
+    let private_shares = dkg::distributed_keygen(master_shares, &group, mpk.params.t).unwrap();
+    let ciphertext = encryption::DIBTDEncryption::encrypt(message, &group.id, mpk).unwrap();
+    (ciphertext, private_shares)
+}
+
+fn decryption_shares(
+    ciphertext: &Ciphertext,
+    private_shares: &HashMap<usize, PrivateKeyShare>,
+    members: &[usize],
+) -> Vec<DecryptionShare> {
+    members
+        .iter()
+        .map(|i| {
+            encryption::DIBTDEncryption::share_decrypt(ciphertext, &private_shares[i]).unwrap()
+        })
+        .collect()
+}
+
+/// An arbitrary fixed point used to corrupt shares
+fn offset_point() -> Point {
+    Curve::mul_generator(&Curve::scalar_reduce(&[1u8; 32])).unwrap()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(24))]
+
+    #[test]
+    fn decryption_succeeds_with_any_quorum(
+        (t, n, members) in group_with_quorum(),
+        message in proptest::collection::vec(any::<u8>(), 0..=256),
+    ) {
+        let (ciphertext, private_shares) = encrypt_for_group(t, n, &message);
+        let shares = decryption_shares(&ciphertext, &private_shares, &members);
+
+        let decrypted = encryption::DIBTDEncryption::decrypt(&ciphertext, &shares, t).unwrap();
+        prop_assert_eq!(decrypted, message);
+    }
+
+    #[test]
+    fn decryption_fails_below_threshold(
+        (dkg_t, dkg_n) in dkg_params(),
+        (t, n) in group_params().prop_filter("needs t >= 2", |(t, _)| *t >= 2),
+        message in proptest::collection::vec(any::<u8>(), 1..=64),
+        seed in any::<u64>(),
+    ) {
+        let (mpk, master_shares) = run_dkg(dkg_n, dkg_t);
+        let group = GroupIdentity {
+            id: format!("prop_group_{}_{}", t, n),
+            threshold: t,
+            members: n,
+        };
+        let private_shares = dkg::distributed_keygen(&master_shares, &group, dkg_t).unwrap();
+        let ciphertext = encryption::DIBTDEncryption::encrypt(&message, &group.id, &mpk).unwrap();
+        let members: Vec<usize> = (1..=n).cycle().skip(seed as usize % n).take(t).collect();
+        let shares = decryption_shares(&ciphertext, &private_shares, &members);
+        let points: Vec<(usize, Point)> = shares.iter().map(|s| (s.index, s.lambda_i)).collect();
+
+        // The length check rejects t - 1 shares outright
+        let result = encryption::DIBTDEncryption::decrypt(&ciphertext, &shares[..t - 1], t);
+        prop_assert!(matches!(result, Err(DIBTDError::InsufficientShares(got, need)) if got == t - 1 && need == t));
+
+        // Interpolated as a quorum of their own, t - 1 shares miss Δ
+        let delta = threshold::Quorum::new(&members).unwrap().combine(&points).unwrap();
+        let partial = threshold::Quorum::new(&members[..t - 1])
+            .unwrap()
+            .combine(&points[..t - 1]);
+        prop_assert!(partial.map_or(true, |point| point != delta));
+        prop_assert!(encryption::DIBTDEncryption::decrypt(&ciphertext, &shares[..t - 1], t - 1).is_err());
+    }
+
+    #[test]
+    fn decryption_fails_with_corrupted_share(
+        (t, n, members) in group_with_quorum(),
+        message in proptest::collection::vec(any::<u8>(), 1..=64),
+        position in any::<prop::sample::Index>(),
+    ) {
+        let (ciphertext, private_shares) = encrypt_for_group(t, n, &message);
+        let mut shares = decryption_shares(&ciphertext, &private_shares, &members);
+
+        // Only the first t shares are combined, so corrupt one of those
+        let victim = position.index(t);
+        shares[victim].lambda_i = Curve::add(&shares[victim].lambda_i, &offset_point()).unwrap();
+
+        prop_assert!(encryption::DIBTDEncryption::decrypt(&ciphertext, &shares, t).is_err());
+    }
+
+    #[test]
+    fn reconstruct_secret_is_subset_independent(
+        (t, n, first) in group_with_quorum(),
+        second in any::<prop::sample::Index>(),
+        secret_seed in any::<u32>(),
+    ) {
+        let secret = utils::scalar_from_u32(secret_seed);
+        let poly = Polynomial::with_constant(t - 1, secret);
+        let shares: Vec<(usize, Scalar)> = (1..=n).map(|i| (i, poly.evaluate(i))).collect();
+
+        // A second quorum: a rotation of all members, cut to a random size >= t
+        let offset = second.index(n);
+        let size = t + second.index(n - t + 1);
+        let other: Vec<(usize, Scalar)> =
+            shares.iter().cycle().skip(offset).take(size).copied().collect();
+        let subset: Vec<(usize, Scalar)> = first.iter().map(|&i| shares[i - 1]).collect();
+
+        let from_first = threshold::ThresholdOperations::reconstruct_secret(&subset, t).unwrap();
+        let from_second = threshold::ThresholdOperations::reconstruct_secret(&other, t).unwrap();
+        prop_assert_eq!(from_first, secret);
+        prop_assert_eq!(from_second, secret);
+    }
+}
@@ -0,0 +1,527 @@
+//! Known-answer test vectors for the DIBTD primitives.
+//!
+//! `tests/vectors/dibtd_kat.json` pins the exact outputs of the hash
+//! functions, seeded DKG, encryption, share decryption with its share
+//! proofs, and Schnorr proofs, together with the ciphersuite they are for.
+//! All randomness comes from ChaCha20 seeded with the 32-byte `seed` of each
+//! vector, so other implementations of the scheme can replay them.
+//!
+//! Regenerate after an intentional format change with
+//! `cargo test --test test_vectors -- --ignored generate_known_answer_vectors`.
+//! The vectors are for secp256k1, so they only run without the `p256` and
+//! `ristretto` features.
+
+#![cfg(not(any(feature = "p256", feature = "ristretto")))]
+
+use dibtd_ephr::group::Group;
+use dibtd_ephr::utils::{from_hex, to_hex};
+use dibtd_ephr::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use secp256k1::{PublicKey, Scalar};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const VECTORS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/vectors/dibtd_kat.json");
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct KnownAnswerVectors {
+    ciphersuite: String,
+    hash_h1: Vec<HashVector>,
+    hash_h2: Vec<HashVector>,
+    hash_h3: Vec<H3Vector>,
+    dkg: Vec<DkgVector>,
+    encryption: Vec<EncryptionVector>,
+    schnorr: Vec<SchnorrVector>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct HashVector {
+    input: String,
+    output: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct H3Vector {
+    p1: String,
+    p2: String,
+    data: String,
+    label: String,
+    output: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct DkgVector {
+    seed: String,
+    n: usize,
+    t: usize,
+    group_id: String,
+    group_threshold: usize,
+    group_members: usize,
+    y: String,
+    gamma: String,
+    key_shares: Vec<KeyShareVector>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct KeyShareVector {
+    index: usize,
+    psi: String,
+    verification_key: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct EncryptionVector {
+    seed: String,
+    group_id: String,
+    label: String,
+    message: String,
+    y: String,
+    gamma: String,
+    threshold: usize,
+    d: String,
+    e: String,
+    f: String,
+    delta: String,
+    shares: Vec<DecryptionShareVector>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct DecryptionShareVector {
+    index: usize,
+    psi: String,
+    lambda: String,
+    proof: ShareProofVector,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct ShareProofVector {
+    a_1: String,
+    a_2: String,
+    response: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SchnorrVector {
+    seed: String,
+    secret: String,
+    context: String,
+    r: String,
+    mu: String,
+}
+
+fn seed(byte: u8) -> [u8; 32] {
+    let mut seed = [0u8; 32];
+    for (i, b) in seed.iter_mut().enumerate() {
+        *b = byte.wrapping_add(i as u8);
+    }
+    seed
+}
+
+fn rng_from_hex(seed: &str) -> ChaCha20Rng {
+    let bytes: [u8; 32] = from_hex(seed)
+        .unwrap()
+        .try_into()
+        .expect("seed must be 32 bytes");
+    ChaCha20Rng::from_seed(bytes)
+}
+
+fn scalar_from_hex(hex: &str) -> Scalar {
+    let bytes: [u8; 32] = from_hex(hex).unwrap().try_into().unwrap();
+    Scalar::from_be_bytes(bytes).unwrap()
+}
+
+fn point_from_hex(hex: &str) -> PublicKey {
+    PublicKey::from_slice(&from_hex(hex).unwrap()).unwrap()
+}
+
+fn point_to_hex(point: &PublicKey) -> String {
+    to_hex(&point.serialize())
+}
+
+fn point_for(scalar_byte: u8) -> PublicKey {
+    let secp = secp256k1::Secp256k1::new();
+    let mut bytes = [0u8; 32];
+    bytes[31] = scalar_byte;
+    let sk = secp256k1::SecretKey::from_slice(&bytes).unwrap();
+    PublicKey::from_secret_key(&secp, &sk)
+}
+
+fn h1_vector(input: &[u8]) -> HashVector {
+    HashVector {
+        input: to_hex(input),
+        output: to_hex(&utils::hash_h1(input, &Ciphersuite::default()).to_be_bytes()),
+    }
+}
+
+fn h2_vector(point: &PublicKey) -> HashVector {
+    HashVector {
+        input: point_to_hex(point),
+        output: to_hex(&utils::hash_h2(point, &Ciphersuite::default())),
+    }
+}
+
+fn h3_vector(p1: &PublicKey, p2: &PublicKey, data: &[u8], label: &[u8]) -> H3Vector {
+    H3Vector {
+        p1: point_to_hex(p1),
+        p2: point_to_hex(p2),
+        data: to_hex(data),
+        label: to_hex(label),
+        output: to_hex(&utils::hash_h3(p1, p2, data, label, &Ciphersuite::default()).to_be_bytes()),
+    }
+}
+
+fn dkg_vector(
+    seed: &str,
+    n: usize,
+    t: usize,
+    group: &GroupIdentity,
+) -> (DkgVector, MasterPublicKey, HashMap<usize, PrivateKeyShare>) {
+    let mut rng = rng_from_hex(seed);
+
+    let mut dkg = dkg::DKGProtocol::new(n, t).unwrap();
+    for i in 1..=n {
+        dkg.init_participant_with_rng(i, &mut rng).unwrap();
+    }
+    for from in 1..=n {
+        for (to, share) in dkg.distribute_shares(from).unwrap() {
+            dkg.receive_shares(to, from, share).unwrap();
+        }
+    }
+    let (mpk, master_shares) = dkg.finalize_with_rng(&mut rng).unwrap();
+    let private_shares =
+        dkg::distributed_keygen_with_rng(&master_shares, group, t, &mut rng).unwrap();
+
+    let mut key_shares: Vec<KeyShareVector> = private_shares
+        .values()
+        .map(|share| KeyShareVector {
+            index: share.index,
+            psi: to_hex(&share.psi_i.to_be_bytes()),
+            verification_key: point_to_hex(&share.verification_key),
+        })
+        .collect();
+    key_shares.sort_by_key(|share| share.index);
+
+    let vector = DkgVector {
+        seed: seed.to_string(),
+        n,
+        t,
+        group_id: group.id.clone(),
+        group_threshold: group.threshold,
+        group_members: group.members,
+        y: point_to_hex(&mpk.y),
+        gamma: point_to_hex(&mpk.gamma),
+        key_shares,
+    };
+    (vector, mpk, private_shares)
+}
+
+fn encryption_vector(
+    seed: &str,
+    group_id: &str,
+    label: &[u8],
+    message: &[u8],
+    mpk: &MasterPublicKey,
+    psis: &[(usize, Scalar)],
+    threshold: usize,
+) -> EncryptionVector {
+    let mut rng = rng_from_hex(seed);
+    let ciphertext = encryption::DIBTDEncryption::encrypt_labeled_with_rng(
+        message, label, group_id, mpk, &mut rng,
+    )
+    .unwrap();
+
+    let shares = psis
+        .iter()
+        .map(|&(index, psi_i)| {
+            let private_share = PrivateKeyShare {
+                index,
+                psi_i,
+                verification_key: group::Curve::mul_generator(&psi_i).unwrap(),
+                ciphersuite: mpk.params.ciphersuite,
+            };
+            let share = encryption::DIBTDEncryption::share_decrypt_labeled(
+                &ciphertext,
+                label,
+                &private_share,
+            )
+            .unwrap();
+            let proof = share.proof.unwrap();
+            DecryptionShareVector {
+                index,
+                psi: to_hex(&psi_i.to_be_bytes()),
+                lambda: point_to_hex(&share.lambda_i),
+                proof: ShareProofVector {
+                    a_1: point_to_hex(&proof.a_1),
+                    a_2: point_to_hex(&proof.a_2),
+                    response: to_hex(&proof.response.to_be_bytes()),
+                },
+            }
+        })
+        .collect();
+
+    EncryptionVector {
+        seed: seed.to_string(),
+        group_id: group_id.to_string(),
+        label: to_hex(label),
+        message: to_hex(message),
+        y: point_to_hex(&mpk.y),
+        gamma: point_to_hex(&mpk.gamma),
+        threshold,
+        d: point_to_hex(&ciphertext.d),
+        e: point_to_hex(&ciphertext.e),
+        f: to_hex(&ciphertext.f),
+        delta: to_hex(&ciphertext.delta.to_be_bytes()),
+        shares,
+    }
+}
+
+fn schnorr_vector(seed: &str, secret: &Scalar, context: &str) -> SchnorrVector {
+    let mut rng = rng_from_hex(seed);
+    let proof =
+        utils::schnorr_prove_with_rng(secret, context, &Ciphersuite::default(), &mut rng).unwrap();
+
+    SchnorrVector {
+        seed: seed.to_string(),
+        secret: to_hex(&secret.to_be_bytes()),
+        context: context.to_string(),
+        r: point_to_hex(&proof.r),
+        mu: to_hex(&proof.mu.to_be_bytes()),
+    }
+}
+
+fn generate_vectors() -> KnownAnswerVectors {
+    let h1_inputs: Vec<Vec<u8>> = vec![
+        Vec::new(),
+        b"cardiology_dept_2024".to_vec(),
+        (0u8..=63).collect(),
+    ];
+    let points = [point_for(1), point_for(2), point_for(3)];
+
+    let groups = [
+        (
+            seed(0x00),
+            3,
+            2,
+            GroupIdentity {
+                id: "cardiology_dept_2024".to_string(),
+                threshold: 2,
+                members: 4,
+            },
+        ),
+        (
+            seed(0x40),
+            5,
+            3,
+            GroupIdentity {
+                id: "radiology".to_string(),
+                threshold: 3,
+                members: 5,
+            },
+        ),
+    ];
+
+    let mut dkg_vectors = Vec::new();
+    let mut encryption_vectors = Vec::new();
+    for (i, (dkg_seed, n, t, group)) in groups.iter().enumerate() {
+        let (vector, mpk, private_shares) = dkg_vector(&to_hex(dkg_seed), *n, *t, group);
+        dkg_vectors.push(vector);
+
+        let psis: Vec<(usize, Scalar)> = (1..=group.threshold)
+            .map(|index| (index, private_shares[&index].psi_i))
+            .collect();
+        let cases: [(&[u8], &[u8]); 2] = [
+            (b"", b"Blood Pressure: 120/80"),
+            (b"5f0c9a7e2b1d4c3a", &[0xA5u8; 32]),
+        ];
+        for (j, (label, message)) in cases.iter().enumerate() {
+            encryption_vectors.push(encryption_vector(
+                &to_hex(&seed(0x80 + (i * 2 + j) as u8)),
+                &group.id,
+                label,
+                message,
+                &mpk,
+                &psis,
+                group.threshold,
+            ));
+        }
+    }
+
+    KnownAnswerVectors {
+        ciphersuite: Ciphersuite::default().to_string(),
+        hash_h1: h1_inputs.iter().map(|input| h1_vector(input)).collect(),
+        hash_h2: points.iter().map(h2_vector).collect(),
+        hash_h3: vec![
+            h3_vector(&points[0], &points[1], b"", b""),
+            h3_vector(&points[1], &points[2], b"ciphertext body", b""),
+            h3_vector(&points[1], &points[2], b"ciphertext body", b"record-0001"),
+        ],
+        dkg: dkg_vectors,
+        encryption: encryption_vectors,
+        schnorr: vec![
+            schnorr_vector(
+                &to_hex(&seed(0xC0)),
+                &Scalar::from_be_bytes(seed(0x01)).unwrap(),
+                "decryption_proof",
+            ),
+            schnorr_vector(&to_hex(&seed(0xD0)), &utils::scalar_from_u32(42), ""),
+        ],
+    }
+}
+
+fn load_vectors() -> KnownAnswerVectors {
+    let json = std::fs::read_to_string(VECTORS_PATH).expect("Test vector file missing");
+    serde_json::from_str(&json).expect("Malformed test vector file")
+}
+
+#[test]
+fn test_vector_ciphersuite() {
+    let vectors = load_vectors();
+    assert_eq!(vectors.ciphersuite, "secp256k1-SHA256-AESGCM");
+    assert_eq!(
+        vectors.ciphersuite.parse::<Ciphersuite>().unwrap(),
+        Ciphersuite::default()
+    );
+}
+
+#[test]
+fn test_hash_vectors() {
+    let vectors = load_vectors();
+
+    for vector in &vectors.hash_h1 {
+        assert_eq!(&h1_vector(&from_hex(&vector.input).unwrap()), vector);
+    }
+    for vector in &vectors.hash_h2 {
+        assert_eq!(&h2_vector(&point_from_hex(&vector.input)), vector);
+    }
+    for vector in &vectors.hash_h3 {
+        let data = from_hex(&vector.data).unwrap();
+        let label = from_hex(&vector.label).unwrap();
+        assert_eq!(
+            &h3_vector(
+                &point_from_hex(&vector.p1),
+                &point_from_hex(&vector.p2),
+                &data,
+                &label
+            ),
+            vector
+        );
+    }
+}
+
+#[test]
+fn test_dkg_vectors() {
+    for vector in &load_vectors().dkg {
+        let group = GroupIdentity {
+            id: vector.group_id.clone(),
+            threshold: vector.group_threshold,
+            members: vector.group_members,
+        };
+        let (computed, _, _) = dkg_vector(&vector.seed, vector.n, vector.t, &group);
+        assert_eq!(&computed, vector);
+    }
+}
+
+#[test]
+fn test_encryption_vectors() {
+    let vectors = load_vectors();
+    let suite: Ciphersuite = vectors.ciphersuite.parse().unwrap();
+    for vector in &vectors.encryption {
+        let mpk = MasterPublicKey {
+            y: point_from_hex(&vector.y),
+            gamma: point_from_hex(&vector.gamma),
+            params: SystemParams {
+                n: 0,
+                t: 0,
+                ciphersuite: suite,
+            },
+        };
+        let psis: Vec<(usize, Scalar)> = vector
+            .shares
+            .iter()
+            .map(|share| (share.index, scalar_from_hex(&share.psi)))
+            .collect();
+        let label = from_hex(&vector.label).unwrap();
+        let message = from_hex(&vector.message).unwrap();
+
+        let computed = encryption_vector(
+            &vector.seed,
+            &vector.group_id,
+            &label,
+            &message,
+            &mpk,
+            &psis,
+            vector.threshold,
+        );
+        assert_eq!(&computed, vector);
+
+        // The pinned shares carry verifying proofs and combine back to the
+        // pinned message
+        let ciphertext = Ciphertext {
+            d: point_from_hex(&vector.d),
+            e: point_from_hex(&vector.e),
+            f: from_hex(&vector.f).unwrap(),
+            delta: scalar_from_hex(&vector.delta),
+            ciphersuite: suite,
+        };
+        let shares: Vec<DecryptionShare> = vector
+            .shares
+            .iter()
+            .map(|share| DecryptionShare {
+                index: share.index,
+                lambda_i: point_from_hex(&share.lambda),
+                proof: Some(ShareProof {
+                    a_1: point_from_hex(&share.proof.a_1),
+                    a_2: point_from_hex(&share.proof.a_2),
+                    response: scalar_from_hex(&share.proof.response),
+                }),
+                ciphersuite: suite,
+            })
+            .collect();
+        for (share, pinned) in shares.iter().zip(&vector.shares) {
+            let verification_key =
+                group::Curve::mul_generator(&scalar_from_hex(&pinned.psi)).unwrap();
+            assert!(crypto::ZKProof::verify_decryption_share(
+                share,
+                &verification_key,
+                &ciphertext
+            ));
+        }
+        let decrypted = encryption::DIBTDEncryption::decrypt_labeled(
+            &ciphertext,
+            &label,
+            &shares,
+            vector.threshold,
+        )
+        .unwrap();
+        assert_eq!(decrypted, message);
+    }
+}
+
+#[test]
+fn test_schnorr_vectors() {
+    for vector in &load_vectors().schnorr {
+        let secret = scalar_from_hex(&vector.secret);
+        assert_eq!(
+            &schnorr_vector(&vector.seed, &secret, &vector.context),
+            vector
+        );
+    }
+}
+
+#[test]
+fn test_vector_file_is_current() {
+    assert_eq!(
+        load_vectors(),
+        generate_vectors(),
+        "Known-answer vectors are out of date; if the format change is intentional, regenerate them"
+    );
+}
+
+#[test]
+#[ignore]
+fn generate_known_answer_vectors() {
+    let json = serde_json::to_string_pretty(&generate_vectors()).unwrap();
+    std::fs::create_dir_all(std::path::Path::new(VECTORS_PATH).parent().unwrap()).unwrap();
+    std::fs::write(VECTORS_PATH, json + "\n").unwrap();
+}
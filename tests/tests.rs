@@ -1,5 +1,6 @@
 #[cfg(test)]
 mod tests {
+    use dibtd_ephr::group::Group;
     use dibtd_ephr::*;
     use std::collections::HashMap;
 
@@ -7,7 +8,15 @@ mod tests {
         n: usize,
         t: usize,
     ) -> Result<(MasterPublicKey, HashMap<usize, MasterSecretShare>)> {
-        let mut dkg = dkg::DKGProtocol::new(n, t)?;
+        setup_test_system_with_suite(n, t, Ciphersuite::default())
+    }
+
+    fn setup_test_system_with_suite(
+        n: usize,
+        t: usize,
+        ciphersuite: Ciphersuite,
+    ) -> Result<(MasterPublicKey, HashMap<usize, MasterSecretShare>)> {
+        let mut dkg = dkg::DKGProtocol::with_ciphersuite(n, t, ciphersuite)?;
 
         for i in 1..=n {
             dkg.init_participant(i)?;
@@ -28,7 +37,7 @@ mod tests {
         }
 
         for i in 1..=n {
-            assert!(dkg.verify_shares(i)?);
+            dkg.verify_shares(i)?;
         }
 
         dkg.finalize()
@@ -238,6 +247,7 @@ mod tests {
             &proof,
             &private_share.verification_key,
             "test_context",
+            &private_share.ciphersuite,
         ));
 
         // Should fail with wrong context
@@ -245,6 +255,7 @@ mod tests {
             &proof,
             &private_share.verification_key,
             "wrong_context",
+            &private_share.ciphersuite,
         ));
     }
 
@@ -299,7 +310,7 @@ mod tests {
             };
 
             let private_shares = dkg::distributed_keygen(&master_shares, &group_id, t)
-                .expect(&format!("Keygen failed for group size {}", members));
+                .unwrap_or_else(|e| panic!("Keygen failed for group size {}: {}", members, e));
 
             assert_eq!(private_shares.len(), members);
 
@@ -362,4 +373,2213 @@ mod tests {
         let result = setup_test_system(1, 1);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_record_store_roundtrip() {
+        let (mpk, master_shares) = setup_test_system(3, 2).expect("DKG setup failed");
+
+        let cardiology = GroupIdentity {
+            id: "cardiology".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let radiology = GroupIdentity {
+            id: "radiology".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let private_shares = dkg::distributed_keygen(&master_shares, &cardiology, 2)
+            .expect("Distributed keygen failed");
+
+        let dir = std::env::temp_dir().join(format!(
+            "dibtd_store_{}_{}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        let mut store = store::RecordStore::open(&dir).expect("Store open failed");
+
+        let metadata = |patient: &str, group: &GroupIdentity, created_at| RecordMetadata {
+            patient_pseudonym: patient.to_string(),
+            record_type: "observation".to_string(),
+            group: group.clone(),
+            created_at,
+        };
+
+        let data = b"Blood Pressure: 118/76 mmHg";
+        let first = store
+            .put(metadata("patient-a", &cardiology, 10), data, &mpk)
+            .expect("Store put failed");
+        store
+            .put(metadata("patient-a", &radiology, 20), b"Chest X-ray", &mpk)
+            .expect("Store put failed");
+        store
+            .put(metadata("patient-b", &cardiology, 30), b"ECG", &mpk)
+            .expect("Store put failed");
+
+        // Indexes survive reopening the store
+        let store = store::RecordStore::open(&dir).expect("Store reopen failed");
+        let patient_a = store.list_by_patient("patient-a").expect("List failed");
+        assert_eq!(patient_a.len(), 2);
+        assert_eq!(patient_a[0].record_id, first.record_id);
+        assert_eq!(
+            store
+                .list_by_group("cardiology")
+                .expect("List failed")
+                .len(),
+            2
+        );
+        assert!(store
+            .list_by_group("oncology")
+            .expect("List failed")
+            .is_empty());
+
+        let shares: Vec<DecryptionShare> = [1, 3]
+            .iter()
+            .map(|i| {
+                store
+                    .share_decrypt(&first.record_id, &private_shares[i])
+                    .expect("Share decryption failed")
+            })
+            .collect();
+        let decrypted = store
+            .decrypt_record(&first.record_id, &shares)
+            .expect("Record decryption failed");
+        assert_eq!(data.to_vec(), decrypted);
+
+        // Points and scalars are stored as hex strings, not byte arrays
+        let stored: serde_json::Value = serde_json::from_slice(
+            &std::fs::read(
+                dir.join("records")
+                    .join(format!("{}.json", first.record_id)),
+            )
+            .expect("Record file missing"),
+        )
+        .expect("Record file is not JSON");
+        let key = &stored["encapsulated_key"];
+        assert_eq!(
+            key["d"].as_str().unwrap(),
+            utils::to_hex(&group::Curve::point_to_bytes(&first.encapsulated_key.d))
+        );
+        assert_eq!(
+            key["delta"].as_str().unwrap(),
+            utils::to_hex(&group::Curve::scalar_to_bytes(
+                &first.encapsulated_key.delta
+            ))
+        );
+        let parsed: Ciphertext = serde_json::from_value(key.clone()).unwrap();
+        assert_eq!(parsed.delta, first.encapsulated_key.delta);
+
+        assert!(matches!(
+            store.get("../../etc/passwd"),
+            Err(DIBTDError::RecordNotFound(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_fhir_field_level_encryption() {
+        let (mpk, master_shares) = setup_test_system(3, 2).expect("DKG setup failed");
+
+        let demographics = GroupIdentity {
+            id: "front_desk".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let clinical = GroupIdentity {
+            id: "cardiology".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let demographics_shares = dkg::distributed_keygen(&master_shares, &demographics, 2)
+            .expect("Distributed keygen failed");
+        let clinical_shares = dkg::distributed_keygen(&master_shares, &clinical, 2)
+            .expect("Distributed keygen failed");
+
+        let original = serde_json::json!({
+            "resourceType": "Patient",
+            "id": "example",
+            "name": [
+                { "family": "Doe", "given": ["Jane"] },
+                { "family": "Roe", "given": ["J."] }
+            ],
+            "birthDate": "1980-02-14",
+            "extension": [{ "url": "diagnosis", "valueString": "Hypertension" }]
+        });
+
+        let policy = FieldPolicy {
+            rules: vec![
+                FieldRule {
+                    path: "name.family".to_string(),
+                    group: demographics.clone(),
+                },
+                FieldRule {
+                    path: "birthDate".to_string(),
+                    group: demographics.clone(),
+                },
+                FieldRule {
+                    path: "extension.0.valueString".to_string(),
+                    group: clinical.clone(),
+                },
+            ],
+        };
+
+        let mut resource = original.clone();
+        let encrypted = fhir::FhirFieldEncryption::encrypt_resource(&mut resource, &policy, &mpk)
+            .expect("Field encryption failed");
+        assert_eq!(encrypted, 4);
+        assert_eq!(resource["name"][0]["given"], original["name"][0]["given"]);
+        assert!(resource["birthDate"]
+            .get(fhir::ENCRYPTED_FIELD_KEY)
+            .is_some());
+
+        // Members produce shares for their own group's fields, keyed by path
+        fn collect_shares(
+            resource: &serde_json::Value,
+            group: &GroupIdentity,
+            members: &HashMap<usize, PrivateKeyShare>,
+            shares: &mut HashMap<String, Vec<DecryptionShare>>,
+        ) {
+            for index in 1..=group.threshold {
+                let member_shares = fhir::FhirFieldEncryption::share_decrypt_resource(
+                    resource,
+                    &group.id,
+                    &members[&index],
+                )
+                .expect("Share decryption failed");
+                for (path, share) in member_shares {
+                    shares.entry(path).or_default().push(share);
+                }
+            }
+        }
+
+        // Shares from the demographics group open only those fields
+        let mut shares = HashMap::new();
+        collect_shares(&resource, &demographics, &demographics_shares, &mut shares);
+        assert_eq!(shares.len(), 3);
+        assert!(shares.contains_key("name.1.family"));
+        let opened = fhir::FhirFieldEncryption::decrypt_resource(&mut resource, &shares)
+            .expect("Field decryption failed");
+        assert_eq!(opened, 3);
+        assert_eq!(resource["name"], original["name"]);
+        assert_eq!(resource["birthDate"], original["birthDate"]);
+        assert!(resource["extension"][0]["valueString"]
+            .get(fhir::ENCRYPTED_FIELD_KEY)
+            .is_some());
+
+        // Below the field's threshold nothing is opened
+        let mut partial = HashMap::new();
+        collect_shares(&resource, &clinical, &clinical_shares, &mut partial);
+        partial
+            .values_mut()
+            .for_each(|field_shares| field_shares.truncate(1));
+        assert!(matches!(
+            fhir::FhirFieldEncryption::decrypt_resource(&mut resource.clone(), &partial),
+            Err(DIBTDError::InsufficientShares(1, 2))
+        ));
+
+        let mut shares = HashMap::new();
+        collect_shares(&resource, &clinical, &clinical_shares, &mut shares);
+        fhir::FhirFieldEncryption::decrypt_resource(&mut resource, &shares)
+            .expect("Field decryption failed");
+        assert_eq!(resource, original);
+
+        // A field moved to another path fails and leaves nothing half-decrypted
+        fhir::FhirFieldEncryption::encrypt_resource(&mut resource, &policy, &mpk)
+            .expect("Field encryption failed");
+        let mut shares = HashMap::new();
+        collect_shares(&resource, &demographics, &demographics_shares, &mut shares);
+        let first = resource["name"][0]["family"].take();
+        resource["name"][0]["family"] = resource["name"][1]["family"].take();
+        resource["name"][1]["family"] = first;
+        let before = resource.clone();
+        assert!(fhir::FhirFieldEncryption::decrypt_resource(&mut resource, &shares).is_err());
+        assert_eq!(resource, before);
+    }
+
+    #[test]
+    fn test_break_glass_access() {
+        let (mpk, master_shares) = setup_test_system(3, 2).expect("DKG setup failed");
+
+        let ward = GroupIdentity {
+            id: "ward_7".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let emergency = GroupIdentity {
+            id: "emergency_on_call".to_string(),
+            threshold: 3,
+            members: 5,
+        };
+        let on_call = dkg::distributed_keygen(&master_shares, &emergency, 2)
+            .expect("Distributed keygen failed");
+        let verification_keys: HashMap<usize, _> = on_call
+            .iter()
+            .map(|(i, share)| (*i, share.verification_key))
+            .collect();
+
+        let dir = std::env::temp_dir().join(format!(
+            "dibtd_break_glass_{}_{}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        let mut store = store::RecordStore::open(&dir).expect("Store open failed");
+
+        let metadata = RecordMetadata {
+            patient_pseudonym: "patient-c".to_string(),
+            record_type: "allergies".to_string(),
+            group: ward.clone(),
+            created_at: 1,
+        };
+
+        // The emergency quorum must be stricter than the normal one
+        assert!(matches!(
+            store.put_with_break_glass(metadata.clone(), &ward, b"Penicillin", &mpk),
+            Err(DIBTDError::InvalidGroupIdentity)
+        ));
+
+        let record = store
+            .put_with_break_glass(metadata, &emergency, b"Penicillin", &mpk)
+            .expect("Store put failed");
+        assert_eq!(record.header.mode, EnvelopeMode::BreakGlass);
+
+        let justification = Justification {
+            requested_by: "dr.on.call".to_string(),
+            reason: "Unconscious patient in ED, allergy check before treatment".to_string(),
+            requested_at: 2,
+        };
+        let approvals: Vec<BreakGlassApproval> = [1, 2, 4]
+            .iter()
+            .map(|i| {
+                breakglass::BreakGlassAccess::approve(&record, &justification, &on_call[i])
+                    .expect("Approval failed")
+            })
+            .collect();
+
+        // Below the emergency threshold nothing is opened or logged
+        assert!(matches!(
+            store.break_glass_decrypt(
+                &record.record_id,
+                &emergency,
+                &justification,
+                &approvals[..2],
+                &verification_keys
+            ),
+            Err(DIBTDError::InsufficientShares(2, 3))
+        ));
+
+        // Approvals signed over a different justification are rejected
+        let mut altered = justification.clone();
+        altered.reason = "Routine review".to_string();
+        assert!(matches!(
+            store.break_glass_decrypt(
+                &record.record_id,
+                &emergency,
+                &altered,
+                &approvals,
+                &verification_keys
+            ),
+            Err(DIBTDError::InvalidShare {
+                index: 1,
+                stage: Stage::ShareVerification
+            })
+        ));
+
+        // The quorum comes from the registered emergency group, and the
+        // header is authenticated, so it cannot be lowered in storage
+        let mut lowered = emergency.clone();
+        lowered.threshold = 2;
+        assert!(matches!(
+            store.break_glass_decrypt(
+                &record.record_id,
+                &lowered,
+                &justification,
+                &approvals[..2],
+                &verification_keys
+            ),
+            Err(DIBTDError::BreakGlassDenied(_))
+        ));
+        let mut tampered = record.clone();
+        tampered.header.emergency_group = Some(lowered);
+        assert!(matches!(
+            store.insert(tampered),
+            Err(DIBTDError::InvalidCiphertext)
+        ));
+        assert!(store
+            .audit_log(&record.record_id)
+            .expect("Audit read failed")
+            .is_empty());
+
+        let (data, entry) = store
+            .break_glass_decrypt(
+                &record.record_id,
+                &emergency,
+                &justification,
+                &approvals,
+                &verification_keys,
+            )
+            .expect("Break-glass decryption failed");
+        assert_eq!(data, b"Penicillin".to_vec());
+        assert_eq!(entry.signatures.len(), 3);
+
+        let log = store
+            .audit_log(&record.record_id)
+            .expect("Audit read failed");
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].justification, justification);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_consent_enforced_before_share_decryption() {
+        let (mpk, master_shares) = setup_test_system(3, 2).expect("DKG setup failed");
+
+        let group = GroupIdentity {
+            id: "oncology".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let private_shares =
+            dkg::distributed_keygen(&master_shares, &group, 2).expect("Distributed keygen failed");
+
+        let dir = std::env::temp_dir().join(format!(
+            "dibtd_consent_{}_{}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        let mut store = store::RecordStore::open(&dir).expect("Store open failed");
+        let metadata = RecordMetadata {
+            patient_pseudonym: "patient-d".to_string(),
+            record_type: "pathology".to_string(),
+            group: group.clone(),
+            created_at: 1,
+        };
+        let record = store
+            .put(metadata.clone(), b"Biopsy: benign", &mpk)
+            .expect("Store put failed");
+        let other = store
+            .put(metadata, b"Biopsy: follow-up", &mpk)
+            .expect("Store put failed");
+
+        let secp = secp256k1::Secp256k1::new();
+        let patient_key = secp256k1::SecretKey::new(&mut rand::thread_rng());
+        let mut guard = consent::ConsentGuard::new();
+        guard.register_patient("patient-d", patient_key.public_key(&secp));
+
+        let grant = ConsentGrant {
+            patient_pseudonym: "patient-d".to_string(),
+            group_id: group.id.clone(),
+            scope: ConsentScope::Records(vec![consent::PatientConsent::consented_record(&record)]),
+            purpose: "treatment".to_string(),
+            issued_at: 100,
+            expires_at: 200,
+        };
+        let consent =
+            consent::PatientConsent::sign(grant.clone(), &patient_key).expect("Signing failed");
+
+        let shares: Vec<DecryptionShare> = [1, 2]
+            .iter()
+            .map(|i| {
+                guard
+                    .share_decrypt(&record, &private_shares[i], &consent, "treatment", 150)
+                    .expect("Share decryption refused")
+            })
+            .collect();
+        assert_eq!(
+            store
+                .decrypt_record(&record.record_id, &shares)
+                .expect("Record decryption failed"),
+            b"Biopsy: benign".to_vec()
+        );
+
+        let denied =
+            |result: Result<DecryptionShare>| matches!(result, Err(DIBTDError::ConsentDenied(_)));
+        // Expired, wrong purpose and out-of-scope record
+        assert!(denied(guard.share_decrypt(
+            &record,
+            &private_shares[&1],
+            &consent,
+            "treatment",
+            200
+        )));
+        assert!(denied(guard.share_decrypt(
+            &record,
+            &private_shares[&1],
+            &consent,
+            "research",
+            150
+        )));
+        assert!(denied(guard.share_decrypt(
+            &other,
+            &private_shares[&1],
+            &consent,
+            "treatment",
+            150
+        )));
+
+        // An encapsulated key swapped into the consented record
+        let mut swapped = record.clone();
+        swapped.encapsulated_key = other.encapsulated_key.clone();
+        assert!(denied(guard.share_decrypt(
+            &swapped,
+            &private_shares[&1],
+            &consent,
+            "treatment",
+            150
+        )));
+
+        // A grant that was altered after signing, or signed by someone else
+        let mut tampered = consent.clone();
+        tampered.grant.scope = ConsentScope::AllRecords;
+        assert!(denied(guard.share_decrypt(
+            &other,
+            &private_shares[&1],
+            &tampered,
+            "treatment",
+            150
+        )));
+        let forged = consent::PatientConsent::sign(
+            ConsentGrant {
+                scope: ConsentScope::AllRecords,
+                ..grant
+            },
+            &secp256k1::SecretKey::new(&mut rand::thread_rng()),
+        )
+        .expect("Signing failed");
+        assert!(denied(guard.share_decrypt(
+            &other,
+            &private_shares[&1],
+            &forged,
+            "treatment",
+            150
+        )));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_seeded_rng_reproducibility() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let run = |seed: u64| {
+            let mut rng = ChaCha20Rng::seed_from_u64(seed);
+            let (n, t) = (3, 2);
+
+            let mut dkg = dkg::DKGProtocol::new(n, t).expect("DKG init failed");
+            for i in 1..=n {
+                dkg.init_participant_with_rng(i, &mut rng)
+                    .expect("Participant init failed");
+            }
+            for from in 1..=n {
+                let shares = dkg.distribute_shares(from).expect("Distribution failed");
+                for (to, share) in shares {
+                    dkg.receive_shares(to, from, share).expect("Receive failed");
+                }
+            }
+            let (mpk, master_shares) = dkg
+                .finalize_with_rng(&mut rng)
+                .expect("DKG finalize failed");
+
+            let group_id = GroupIdentity {
+                id: "test_group".to_string(),
+                threshold: 2,
+                members: 3,
+            };
+            let private_shares =
+                dkg::distributed_keygen_with_rng(&master_shares, &group_id, t, &mut rng)
+                    .expect("Distributed keygen failed");
+
+            let ciphertext = encryption::DIBTDEncryption::encrypt_with_rng(
+                b"Test",
+                &group_id.id,
+                &mpk,
+                &mut rng,
+            )
+            .expect("Encryption failed");
+            let proof = utils::schnorr_prove_with_rng(
+                &private_shares[&1].psi_i,
+                "ctx",
+                &mpk.params.ciphersuite,
+                &mut rng,
+            )
+            .expect("Proof generation failed");
+            let key = aead::AEADCipher::generate_key_with_rng(&mut rng);
+            let nonce = aead::AEADCipher::generate_nonce_with_rng(&mut rng);
+
+            (
+                serde_json::to_string(&mpk).unwrap(),
+                serde_json::to_string(&ciphertext).unwrap(),
+                serde_json::to_string(&proof).unwrap(),
+                key,
+                nonce,
+            )
+        };
+
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn test_malformed_proof_is_an_error() {
+        let suite = Ciphersuite::default();
+        let secret = utils::scalar_from_u32(7);
+        let mut proof =
+            utils::schnorr_prove(&secret, "ctx", &suite).expect("Proof generation failed");
+        let public_key = group::Curve::mul_generator(&secret).unwrap();
+
+        assert!(
+            utils::schnorr_verify(&proof, &public_key, "ctx", &suite).expect("Verification failed")
+        );
+
+        // A zero response used to panic inside schnorr_verify
+        proof.mu = group::Curve::SCALAR_ZERO;
+        assert!(matches!(
+            utils::schnorr_verify(&proof, &public_key, "ctx", &suite),
+            Err(DIBTDError::InvalidProof)
+        ));
+        assert!(!crypto::ZKProof::verify_share(
+            &proof,
+            &public_key,
+            "ctx",
+            &suite
+        ));
+    }
+
+    #[test]
+    fn test_malformed_dkg_input_is_an_error() {
+        let (n, t) = (3, 2);
+        let mut dkg = dkg::DKGProtocol::new(n, t).expect("DKG setup failed");
+        for i in 1..=n {
+            dkg.init_participant(i).expect("Participant init failed");
+        }
+        let shares = dkg.distribute_shares(1).expect("Share distribution failed");
+
+        // Shares claiming to come from outside the committee or from oneself
+        assert!(dkg.receive_shares(2, 0, shares[&2]).is_err());
+        assert!(dkg.receive_shares(2, n + 1, shares[&2]).is_err());
+        assert!(dkg.receive_shares(2, 2, shares[&2]).is_err());
+
+        // A sender whose commitment vector is too short for the receiver
+        dkg.receive_shares(3, 1, shares[&3])
+            .expect("Receiving shares failed");
+        dkg.participants
+            .get_mut(&1)
+            .unwrap()
+            .commitments_0
+            .truncate(2);
+        assert!(matches!(
+            dkg.verify_shares(3),
+            Err(DIBTDError::InvalidShare {
+                index: 1,
+                stage: Stage::KeyGeneration
+            })
+        ));
+
+        // Nothing to aggregate once the threshold has been tampered with
+        dkg.t = 0;
+        assert!(matches!(
+            dkg.finalize(),
+            Err(DIBTDError::DKGProtocolFailed(_))
+        ));
+        assert!(dkg.init_participant(1).is_err());
+    }
+
+    #[test]
+    fn test_malformed_keygen_parameters_are_errors() {
+        let (_, master_shares) = setup_test_system(3, 2).expect("System setup failed");
+        let group = GroupIdentity {
+            id: "cardiology".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+
+        assert!(matches!(
+            dkg::distributed_keygen(&master_shares, &group, 0),
+            Err(DIBTDError::InvalidThreshold(0, 3))
+        ));
+        assert!(matches!(
+            dkg::distributed_keygen(&master_shares, &group, 4),
+            Err(DIBTDError::InsufficientShares(3, 4))
+        ));
+
+        let zero_threshold = GroupIdentity {
+            threshold: 0,
+            ..group.clone()
+        };
+        assert!(dkg::distributed_keygen(&master_shares, &zero_threshold, 2).is_err());
+
+        let too_few_members = GroupIdentity {
+            threshold: 4,
+            ..group
+        };
+        assert!(dkg::distributed_keygen(&master_shares, &too_few_members, 2).is_err());
+    }
+
+    #[test]
+    fn test_errors_carry_stage_index_and_code() {
+        let (mpk, master_shares) = setup_test_system(3, 2).expect("DKG setup failed");
+        let group_id = GroupIdentity {
+            id: "test_group".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let private_shares = dkg::distributed_keygen(&master_shares, &group_id, 2)
+            .expect("Distributed keygen failed");
+        let ciphertext = encryption::DIBTDEncryption::encrypt(b"Test data", &group_id.id, &mpk)
+            .expect("Encryption failed");
+        let share = encryption::DIBTDEncryption::share_decrypt(&ciphertext, &private_shares[&1])
+            .expect("Share decryption failed");
+
+        // The same member's share submitted twice
+        let err =
+            encryption::DIBTDEncryption::decrypt(&ciphertext, &[share.clone(), share.clone()], 2)
+                .unwrap_err();
+        assert!(matches!(err, DIBTDError::DuplicateIndex(1)));
+        assert_eq!(err.code(), "duplicate_index");
+        assert_eq!(err.stage(), Some(Stage::Combination));
+        assert_eq!(err.index(), Some(1));
+
+        // A share from a different member under the wrong index
+        let mut forged =
+            encryption::DIBTDEncryption::share_decrypt(&ciphertext, &private_shares[&2])
+                .expect("Share decryption failed");
+        forged.lambda_i = share.lambda_i;
+        let err =
+            encryption::DIBTDEncryption::decrypt(&ciphertext, &[share.clone(), forged.clone()], 2)
+                .unwrap_err();
+        assert!(matches!(
+            err,
+            DIBTDError::DecryptionFailed {
+                stage: Stage::Combination
+            }
+        ));
+        assert_eq!(err.code(), "decryption_failed");
+        assert_eq!(err.to_string(), "Decryption failed during combination");
+
+        // With the verification keys the proofs are checked before combining,
+        // naming the member at fault
+        let verification_keys: HashMap<usize, group::Point> = private_shares
+            .iter()
+            .map(|(&index, private_share)| (index, private_share.verification_key))
+            .collect();
+        let good = encryption::DIBTDEncryption::share_decrypt(&ciphertext, &private_shares[&3])
+            .expect("Share decryption failed");
+        let err = encryption::DIBTDEncryption::decrypt_verified(
+            &ciphertext,
+            &[good.clone(), forged],
+            2,
+            &verification_keys,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            DIBTDError::InvalidShare {
+                index: 2,
+                stage: Stage::ShareVerification
+            }
+        ));
+        assert_eq!(err.code(), "invalid_share");
+        assert_eq!(err.index(), Some(2));
+        assert_eq!(
+            encryption::DIBTDEncryption::decrypt_verified(
+                &ciphertext,
+                &[good, share.clone()],
+                2,
+                &verification_keys
+            )
+            .expect("Verified decryption failed"),
+            b"Test data"
+        );
+
+        // A ciphertext that fails the well-formedness check
+        let mut tampered = ciphertext.clone();
+        tampered.f[0] ^= 1;
+        let err =
+            encryption::DIBTDEncryption::share_decrypt(&tampered, &private_shares[&1]).unwrap_err();
+        assert_eq!(err.code(), "invalid_ciphertext");
+        assert_eq!(err.stage(), Some(Stage::CiphertextValidation));
+        assert_eq!(err.index(), None);
+
+        assert_eq!(
+            serde_json::to_string(&Stage::ShareVerification).unwrap(),
+            "\"share_verification\""
+        );
+    }
+
+    #[test]
+    fn test_public_ciphertext_validation() {
+        let (mpk, _) = setup_test_system(3, 2).expect("DKG setup failed");
+
+        // Validation needs only the ciphertexts themselves
+        let mut ciphertexts: Vec<Ciphertext> = (0..8)
+            .map(|i| {
+                encryption::DIBTDEncryption::encrypt(
+                    format!("record {}", i).as_bytes(),
+                    "test_group",
+                    &mpk,
+                )
+                .expect("Encryption failed")
+            })
+            .collect();
+        for ciphertext in &ciphertexts {
+            ciphertext.verify().expect("Valid ciphertext rejected");
+        }
+        Ciphertext::batch_verify(&ciphertexts).expect("Valid batch rejected");
+        Ciphertext::batch_verify(&[]).expect("Empty batch rejected");
+
+        ciphertexts[5].f[0] ^= 1;
+        let err = Ciphertext::batch_verify(&ciphertexts).unwrap_err();
+        assert!(matches!(
+            err,
+            DIBTDError::InvalidBatchCiphertext { index: 5 }
+        ));
+        assert_eq!(err.code(), "invalid_ciphertext");
+        assert_eq!(err.index(), Some(5));
+        let bad: Vec<usize> = (0..ciphertexts.len())
+            .filter(|&i| ciphertexts[i].verify().is_err())
+            .collect();
+        assert_eq!(bad, vec![5]);
+        ciphertexts[2].f[0] ^= 1;
+        assert_eq!(
+            Ciphertext::batch_verify(&ciphertexts).unwrap_err().index(),
+            Some(2)
+        );
+        ciphertexts[2].f[0] ^= 1;
+
+        // Swapping proofs between two valid ciphertexts breaks both
+        let mut swapped = ciphertexts[0].clone();
+        swapped.delta = ciphertexts[1].delta;
+        assert!(swapped.verify().is_err());
+        assert!(Ciphertext::batch_verify(&[ciphertexts[1].clone(), swapped]).is_err());
+
+        // The record store refuses replicated records with malformed keys
+        let dir = std::env::temp_dir().join(format!(
+            "dibtd_gateway_{}_{}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        let mut store = store::RecordStore::open(&dir).expect("Store open failed");
+        let metadata = RecordMetadata {
+            patient_pseudonym: "patient-a".to_string(),
+            record_type: "observation".to_string(),
+            group: GroupIdentity {
+                id: "test_group".to_string(),
+                threshold: 2,
+                members: 3,
+            },
+            created_at: 10,
+        };
+        let mut record = store.put(metadata, b"ECG", &mpk).expect("Store put failed");
+        record.record_id = "00ff".to_string();
+        record.encapsulated_key.delta = ciphertexts[0].delta;
+        assert!(matches!(
+            store.insert(record),
+            Err(DIBTDError::InvalidCiphertext)
+        ));
+        assert!(store.get("00ff").is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_ciphertext_label_binding() {
+        let (mpk, master_shares) = setup_test_system(3, 2).expect("DKG setup failed");
+        let group_id = GroupIdentity {
+            id: "test_group".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let private_shares = dkg::distributed_keygen(&master_shares, &group_id, 2)
+            .expect("Distributed keygen failed");
+
+        let message = b"Test medical record data";
+        let ciphertext =
+            encryption::DIBTDEncryption::encrypt_labeled(message, b"record-1", &group_id.id, &mpk)
+                .expect("Encryption failed");
+        ciphertext
+            .verify_labeled(b"record-1")
+            .expect("Valid label rejected");
+
+        // The same ciphertext presented under another label is rejected
+        for label in [&b"record-2"[..], &b""[..]] {
+            assert!(matches!(
+                encryption::DIBTDEncryption::share_decrypt_labeled(
+                    &ciphertext,
+                    label,
+                    &private_shares[&1]
+                ),
+                Err(DIBTDError::InvalidCiphertext)
+            ));
+        }
+        assert!(ciphertext.verify().is_err());
+
+        let shares: Vec<DecryptionShare> = [1, 2]
+            .iter()
+            .map(|i| {
+                encryption::DIBTDEncryption::share_decrypt_labeled(
+                    &ciphertext,
+                    b"record-1",
+                    &private_shares[i],
+                )
+                .expect("Share decryption failed")
+            })
+            .collect();
+        assert!(
+            encryption::DIBTDEncryption::decrypt_labeled(&ciphertext, b"record-2", &shares, 2)
+                .is_err()
+        );
+        let decrypted =
+            encryption::DIBTDEncryption::decrypt_labeled(&ciphertext, b"record-1", &shares, 2)
+                .expect("Decryption failed");
+        assert_eq!(message.to_vec(), decrypted);
+
+        // An encapsulated key copied into a record with a different id
+        let dir = std::env::temp_dir().join(format!(
+            "dibtd_labels_{}_{}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        let mut store = store::RecordStore::open(&dir).expect("Store open failed");
+        let metadata = RecordMetadata {
+            patient_pseudonym: "patient-a".to_string(),
+            record_type: "observation".to_string(),
+            group: group_id.clone(),
+            created_at: 10,
+        };
+        let original = store
+            .put(metadata.clone(), b"ECG", &mpk)
+            .expect("Store put failed");
+        let mut target = store
+            .put(metadata, b"Chest X-ray", &mpk)
+            .expect("Store put failed");
+        target.encapsulated_key = original.encapsulated_key.clone();
+        assert!(matches!(
+            store.insert(target.clone()),
+            Err(DIBTDError::InvalidCiphertext)
+        ));
+        assert!(matches!(
+            encryption::DIBTDEncryption::share_decrypt_labeled(
+                &target.encapsulated_key,
+                &target.associated_data().unwrap(),
+                &private_shares[&1]
+            ),
+            Err(DIBTDError::InvalidCiphertext)
+        ));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_multi_scalar_mul_matches_naive() {
+        use group::Curve;
+        let minus_one = utils::scalar_negate(&Curve::SCALAR_ONE);
+
+        // Sizes on both sides of the Straus and Pippenger thresholds
+        for size in [1u32, 2, 5, 8, 40, 64, 150] {
+            let terms: Vec<(group::Point, group::Scalar)> = (1..=size)
+                .map(|i| {
+                    let point = Curve::mul_generator(&utils::scalar_from_u32(i * 7919)).unwrap();
+                    (point, Curve::scalar_random(&mut rand::thread_rng()))
+                })
+                .collect();
+
+            let naive = terms
+                .iter()
+                .map(|(point, scalar)| Curve::mul(point, scalar).unwrap())
+                .reduce(|acc, p| Curve::add(&acc, &p).unwrap())
+                .unwrap();
+            assert_eq!(msm::multi_scalar_mul(&terms).unwrap(), Some(naive));
+
+            // With the generator term: b * G + Σ s_i * P_i
+            let base = utils::scalar_from_u32(size);
+            let base_point = Curve::mul_generator(&base).unwrap();
+            assert_eq!(
+                msm::multi_scalar_mul_with_base(&base, &terms).unwrap(),
+                Some(Curve::add(&naive, &base_point).unwrap())
+            );
+
+            // Σ s_i * P_i + Σ (-s_i) * P_i is the point at infinity
+            let cancelling: Vec<(group::Point, group::Scalar)> = terms
+                .iter()
+                .flat_map(|&(point, scalar)| {
+                    [
+                        (point, scalar),
+                        (point, utils::scalar_mul(&scalar, &minus_one)),
+                    ]
+                })
+                .collect();
+            assert_eq!(msm::multi_scalar_mul(&cancelling).unwrap(), None);
+        }
+        assert_eq!(msm::multi_scalar_mul(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_batch_proof_verification() {
+        let context = "decryption_proof";
+        let suite = Ciphersuite::default();
+        let (mut proofs, verification_keys): (Vec<Proof>, Vec<group::Point>) = (1..=64u32)
+            .map(|i| {
+                let secret = utils::scalar_from_u32(i * 104729);
+                let proof = utils::schnorr_prove(&secret, context, &suite)
+                    .expect("Proof generation failed");
+                (proof, group::Curve::mul_generator(&secret).unwrap())
+            })
+            .unzip();
+
+        assert!(crypto::ZKProof::batch_verify(
+            &proofs,
+            &verification_keys,
+            context,
+            &suite
+        ));
+        assert!(!crypto::ZKProof::batch_verify(
+            &proofs,
+            &verification_keys,
+            "other_context",
+            &suite
+        ));
+        assert!(!crypto::ZKProof::batch_verify(
+            &proofs[1..],
+            &verification_keys,
+            context,
+            &suite
+        ));
+        assert!(
+            crypto::ZKProof::find_invalid(&proofs, &verification_keys, context, &suite).is_empty()
+        );
+
+        // Two proofs swapped between members and one with a zero response
+        proofs.swap(3, 40);
+        proofs[57].mu = group::Curve::SCALAR_ZERO;
+        assert!(!crypto::ZKProof::batch_verify(
+            &proofs,
+            &verification_keys,
+            context,
+            &suite
+        ));
+        assert_eq!(
+            crypto::ZKProof::find_invalid(&proofs, &verification_keys, context, &suite),
+            vec![3, 40, 57]
+        );
+
+        // Decryption share proofs batch the same way, both equations at once
+        let (mpk, master_shares) = setup_test_system(3, 2).expect("DKG setup failed");
+        let group_id = GroupIdentity {
+            id: "radiology".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let private_shares = dkg::distributed_keygen(&master_shares, &group_id, 2)
+            .expect("Distributed keygen failed");
+        let ciphertext = encryption::DIBTDEncryption::encrypt(b"Test data", &group_id.id, &mpk)
+            .expect("Encryption failed");
+        let mut shares: Vec<(DecryptionShare, group::Point)> = [1, 2, 3]
+            .iter()
+            .map(|i| {
+                let share =
+                    encryption::DIBTDEncryption::share_decrypt(&ciphertext, &private_shares[i])
+                        .expect("Share decryption failed");
+                (share, private_shares[i].verification_key)
+            })
+            .collect();
+        fn items(
+            shares: &[(DecryptionShare, group::Point)],
+        ) -> Vec<(&DecryptionShare, &group::Point)> {
+            shares.iter().map(|(share, vk)| (share, vk)).collect()
+        }
+        assert!(crypto::ZKProof::batch_verify_decryption_shares(
+            &ciphertext,
+            &items(&shares)
+        ));
+        assert!(crypto::ZKProof::verify_decryption_share(
+            &shares[0].0,
+            &shares[0].1,
+            &ciphertext
+        ));
+
+        // Share generation is deterministic, proof included
+        let again = encryption::DIBTDEncryption::share_decrypt(&ciphertext, &private_shares[&1])
+            .expect("Share decryption failed");
+        assert_eq!(again.proof, shares[0].0.proof);
+
+        // A wrong Λ_i breaks the D equation, a missing proof fails outright
+        shares[1].0.lambda_i = shares[0].0.lambda_i;
+        shares[2].0.proof = None;
+        assert!(!crypto::ZKProof::batch_verify_decryption_shares(
+            &ciphertext,
+            &items(&shares)
+        ));
+        assert_eq!(
+            crypto::ZKProof::find_invalid_decryption_shares(&ciphertext, &items(&shares)),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn test_inconsistent_dkg_commitments_rejected() {
+        let (n, t) = (5, 3);
+        let mut dkg = dkg::DKGProtocol::new(n, t).expect("DKG setup failed");
+        for i in 1..=n {
+            dkg.init_participant(i).expect("Participant init failed");
+        }
+        for from in 1..=n {
+            for (to, share) in dkg
+                .distribute_shares(from)
+                .expect("Share distribution failed")
+            {
+                dkg.receive_shares(to, from, share)
+                    .expect("Receiving shares failed");
+            }
+        }
+        for i in 1..=n {
+            assert!(dkg.verify_commitments(i).expect("Commitment check failed"));
+        }
+
+        // Participant 2 commits to a value off its degree t - 1 polynomial
+        let participant = dkg.participants.get_mut(&2).unwrap();
+        participant.commitments_1[4] = participant.commitments_1[0];
+
+        assert!(!dkg.verify_commitments(2).expect("Commitment check failed"));
+        assert!(dkg.verify_commitments(1).expect("Commitment check failed"));
+        let err = dkg.finalize().unwrap_err();
+        assert!(matches!(
+            err,
+            DIBTDError::InvalidShare {
+                index: 2,
+                stage: Stage::KeyGeneration
+            }
+        ));
+        assert_eq!(err.index(), Some(2));
+
+        // A share that does not match its sender's commitments
+        let participant = dkg.participants.get_mut(&4).unwrap();
+        let (share_0, share_1) = participant.shares_received[&5];
+        participant.shares_received.insert(
+            5,
+            (
+                group::Curve::scalar_add(&share_0, &group::Curve::SCALAR_ONE),
+                share_1,
+            ),
+        );
+        assert_eq!(dkg.verify_shares(4).unwrap_err().index(), Some(5));
+        dkg.verify_shares(3).expect("Share verification failed");
+    }
+
+    #[test]
+    fn test_quorum_decryption() {
+        let (mpk, master_shares) = setup_test_system(5, 3).expect("DKG setup failed");
+        let group_id = GroupIdentity {
+            id: "test_group".to_string(),
+            threshold: 3,
+            members: 5,
+        };
+        let private_shares = dkg::distributed_keygen(&master_shares, &group_id, 3)
+            .expect("Distributed keygen failed");
+
+        let quorum = threshold::Quorum::new(&[4, 1, 5]).expect("Quorum setup failed");
+        for &i in quorum.indices() {
+            assert_eq!(
+                quorum.coefficient(i),
+                Some(utils::lagrange_coefficient(&[4, 1, 5], i, 0).unwrap())
+            );
+        }
+        assert_eq!(quorum.coefficient(2), None);
+
+        let messages: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 32]).collect();
+        let ciphertexts: Vec<Ciphertext> = messages
+            .iter()
+            .map(|m| {
+                encryption::DIBTDEncryption::encrypt_labeled(m, b"batch", &group_id.id, &mpk)
+                    .expect("Encryption failed")
+            })
+            .collect();
+
+        // Shares arrive in any order and may include non-members
+        let shares: Vec<Vec<DecryptionShare>> = ciphertexts
+            .iter()
+            .map(|ct| {
+                [5, 2, 1, 4]
+                    .iter()
+                    .map(|i| {
+                        encryption::DIBTDEncryption::share_decrypt_labeled(
+                            ct,
+                            b"batch",
+                            &private_shares[i],
+                        )
+                        .expect("Share decryption failed")
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let items: Vec<(&Ciphertext, &[u8], &[DecryptionShare])> = ciphertexts
+            .iter()
+            .zip(&shares)
+            .map(|(ct, s)| (ct, &b"batch"[..], s.as_slice()))
+            .collect();
+        let decrypted = encryption::DIBTDEncryption::decrypt_batch_labeled(&items, &quorum)
+            .expect("Batch decryption failed");
+        assert_eq!(decrypted, messages);
+
+        // Every member must contribute exactly once
+        assert!(matches!(
+            encryption::DIBTDEncryption::decrypt_labeled_with_quorum(
+                &ciphertexts[0],
+                b"batch",
+                &shares[0][..3],
+                &quorum
+            ),
+            Err(DIBTDError::InsufficientShares(2, 3))
+        ));
+        let mut repeated = shares[0].clone();
+        repeated.push(shares[0][0].clone());
+        assert!(matches!(
+            encryption::DIBTDEncryption::decrypt_labeled_with_quorum(
+                &ciphertexts[0],
+                b"batch",
+                &repeated,
+                &quorum
+            ),
+            Err(DIBTDError::DuplicateIndex(5))
+        ));
+        assert!(matches!(
+            threshold::Quorum::new(&[1, 3, 1]),
+            Err(DIBTDError::DuplicateIndex(1))
+        ));
+        assert!(matches!(
+            threshold::Quorum::new(&[]),
+            Err(DIBTDError::InsufficientShares(0, 1))
+        ));
+    }
+
+    #[test]
+    fn test_batch_encryption_and_share_generation() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let (mpk, master_shares) = setup_test_system(3, 2).expect("DKG setup failed");
+        let group_id = GroupIdentity {
+            id: "test_group".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let private_shares = dkg::distributed_keygen(&master_shares, &group_id, 2)
+            .expect("Distributed keygen failed");
+
+        let records: Vec<Vec<u8>> = (0..12u8).map(|i| vec![i; 32]).collect();
+        let messages: Vec<&[u8]> = records.iter().map(|r| r.as_slice()).collect();
+
+        // The output depends only on the seed, not on scheduling
+        let encrypt = || {
+            encryption::DIBTDEncryption::encrypt_many_with_rng(
+                &messages,
+                &group_id.id,
+                &mpk,
+                &mut ChaCha20Rng::seed_from_u64(41),
+            )
+            .expect("Batch encryption failed")
+        };
+        let ciphertexts = encrypt();
+        assert_eq!(
+            serde_json::to_string(&ciphertexts).unwrap(),
+            serde_json::to_string(&encrypt()).unwrap()
+        );
+
+        let share_batches: Vec<Vec<DecryptionShare>> = [1, 2]
+            .iter()
+            .map(|i| {
+                encryption::DIBTDEncryption::share_decrypt_many(&ciphertexts, &private_shares[i])
+                    .expect("Batch share decryption failed")
+            })
+            .collect();
+        for (k, ciphertext) in ciphertexts.iter().enumerate() {
+            let single =
+                encryption::DIBTDEncryption::share_decrypt(ciphertext, &private_shares[&1])
+                    .expect("Share decryption failed");
+            assert_eq!(share_batches[0][k].lambda_i, single.lambda_i);
+
+            let shares = vec![share_batches[0][k].clone(), share_batches[1][k].clone()];
+            let decrypted = encryption::DIBTDEncryption::decrypt(ciphertext, &shares, 2)
+                .expect("Decryption failed");
+            assert_eq!(decrypted, records[k]);
+        }
+
+        // One failing proof is reported at its own position
+        let context = "decryption_proof";
+        let proofs: Vec<Proof> = (0..ciphertexts.len())
+            .map(|k| {
+                crypto::ZKProof::prove_share(&private_shares[&1], &share_batches[0][k], context)
+                    .expect("Proof generation failed")
+            })
+            .collect();
+        let mut keys = vec![private_shares[&1].verification_key; proofs.len()];
+        keys[7] = private_shares[&2].verification_key;
+        let items: Vec<(&Proof, &group::Point)> = proofs.iter().zip(&keys).collect();
+        let results =
+            crypto::ZKProof::verify_shares_many(&items, context, &private_shares[&1].ciphersuite);
+        assert_eq!(results.iter().position(|ok| !ok), Some(7));
+        assert_eq!(results.iter().filter(|ok| !**ok).count(), 1);
+    }
+
+    #[test]
+    fn test_streaming_aead() {
+        use std::io::{Read, Write};
+
+        let key = aead::AEADCipher::generate_key();
+        let seal = |plaintext: &[u8]| {
+            let mut encryptor =
+                stream::StreamEncryptor::with_chunk_size(&key, b"study-7", 1000, Vec::new())
+                    .expect("Stream setup failed");
+            // Uneven writes exercise the chunk buffering
+            for piece in plaintext.chunks(777) {
+                encryptor.write_all(piece).expect("Stream write failed");
+            }
+            encryptor.finish().expect("Stream finish failed")
+        };
+        let open = |sealed: &[u8], associated_data: &[u8]| {
+            let mut plaintext = Vec::new();
+            stream::StreamDecryptor::new(&key, associated_data, sealed)
+                .and_then(|mut d| {
+                    d.read_to_end(&mut plaintext)
+                        .map_err(|e| DIBTDError::AEADError(e.to_string()))
+                })
+                .map(|_| plaintext)
+        };
+
+        // Empty, partial, exact multiple and ragged last chunk
+        for len in [0, 999, 1000, 5000, 10_123] {
+            let plaintext: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let sealed = seal(&plaintext);
+            assert_eq!(
+                open(&sealed, b"study-7").expect("Stream decryption failed"),
+                plaintext
+            );
+        }
+
+        let plaintext = vec![0x42u8; 3500];
+        let sealed = seal(&plaintext);
+        let header = stream::header_len(aead::AeadAlgorithm::Aes256Gcm);
+        let block = 1000 + 16;
+        assert!(open(&sealed, b"study-8").is_err());
+
+        // Dropping the final chunk, or cutting on a chunk boundary, is detected
+        assert!(open(&sealed[..header + 3 * block], b"study-7").is_err());
+        assert!(open(&sealed[..header + 2 * block], b"study-7").is_err());
+
+        // Swapping two chunks is detected
+        let mut reordered = sealed.clone();
+        reordered[header..header + block]
+            .copy_from_slice(&sealed[header + block..header + 2 * block]);
+        reordered[header + block..header + 2 * block]
+            .copy_from_slice(&sealed[header..header + block]);
+        assert!(open(&reordered, b"study-7").is_err());
+
+        // A tampered chunk never releases its bytes, even when read again
+        let mut tampered = sealed.clone();
+        tampered[header + block + 5] ^= 1;
+        let mut decryptor = stream::StreamDecryptor::new(&key, b"study-7", &tampered[..])
+            .expect("Stream setup failed");
+        let mut chunk = vec![0u8; 1000];
+        decryptor
+            .read_exact(&mut chunk)
+            .expect("First chunk rejected");
+        assert_eq!(chunk, plaintext[..1000]);
+        let mut buffer = [0u8; 64];
+        assert!(decryptor.read(&mut buffer).is_err());
+        assert!(decryptor.read(&mut buffer).is_err());
+        assert!(decryptor.read(&mut buffer).is_err());
+
+        // The header commits to the key, so another key is refused up front
+        let mut other_key = key;
+        other_key[0] ^= 1;
+        assert!(stream::StreamDecryptor::new(&other_key, b"study-7", &sealed[..]).is_err());
+        let mut recommitted = sealed.clone();
+        recommitted[header - 1] ^= 1;
+        assert!(stream::StreamDecryptor::new(&key, b"study-7", &recommitted[..]).is_err());
+
+        // Sealing a whole study for a group
+        let (mpk, master_shares) = setup_test_system(3, 2).expect("DKG setup failed");
+        let group_id = GroupIdentity {
+            id: "radiology".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let private_shares = dkg::distributed_keygen(&master_shares, &group_id, 2)
+            .expect("Distributed keygen failed");
+
+        let study: Vec<u8> = (0..200_000u32).map(|i| (i * 7) as u8).collect();
+        let mut sealed_study = Vec::new();
+        let encapsulated_key = EnvelopeEncryption::seal_stream(
+            &study[..],
+            &mut sealed_study,
+            b"study-7",
+            &group_id.id,
+            &mpk,
+        )
+        .expect("Stream sealing failed");
+
+        let shares: Vec<DecryptionShare> = [1, 3]
+            .iter()
+            .map(|i| {
+                encryption::DIBTDEncryption::share_decrypt_labeled(
+                    &encapsulated_key,
+                    b"study-7",
+                    &private_shares[i],
+                )
+                .expect("Share decryption failed")
+            })
+            .collect();
+
+        let mut opened = Vec::new();
+        let written = EnvelopeEncryption::open_stream(
+            &encapsulated_key,
+            &sealed_study[..],
+            &mut opened,
+            b"study-7",
+            &shares,
+            2,
+        )
+        .expect("Stream opening failed");
+        assert_eq!(written, study.len() as u64);
+        assert_eq!(opened, study);
+
+        // The encapsulated key only opens the stream it was sealed with
+        let mut other_study = Vec::new();
+        EnvelopeEncryption::seal_stream(
+            &study[..],
+            &mut other_study,
+            b"study-7",
+            &group_id.id,
+            &mpk,
+        )
+        .expect("Stream sealing failed");
+        assert!(EnvelopeEncryption::open_stream(
+            &encapsulated_key,
+            &other_study[..],
+            std::io::sink(),
+            b"study-7",
+            &shares,
+            2
+        )
+        .is_err());
+
+        // A header whose key commitment is wrong is refused before any chunk
+        // is decrypted
+        let header = stream::header_len(mpk.params.ciphersuite.aead);
+        let mut recommitted = sealed_study.clone();
+        recommitted[header - 1] ^= 1;
+        let mut released = Vec::new();
+        assert!(EnvelopeEncryption::open_stream(
+            &encapsulated_key,
+            &recommitted[..],
+            &mut released,
+            b"study-7",
+            &shares,
+            2
+        )
+        .is_err());
+        assert!(released.is_empty());
+    }
+
+    #[test]
+    fn test_aead_algorithms() {
+        let group_id = GroupIdentity {
+            id: "test_group".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let plaintext = b"Patient: John Doe\nHeart Rate: 72 bpm";
+
+        // Envelopes use the AEAD algorithm of the system's ciphersuite
+        for algorithm in [
+            aead::AeadAlgorithm::Aes256Gcm,
+            aead::AeadAlgorithm::ChaCha20Poly1305,
+            aead::AeadAlgorithm::XChaCha20Poly1305,
+            aead::AeadAlgorithm::Aes256GcmSiv,
+        ] {
+            let (mpk, master_shares) =
+                setup_test_system_with_suite(3, 2, Ciphersuite::with_aead(algorithm))
+                    .expect("DKG setup failed");
+            let private_shares = dkg::distributed_keygen(&master_shares, &group_id, 2)
+                .expect("Distributed keygen failed");
+            let (keys, packet) =
+                EnvelopeEncryption::seal_for_groups(plaintext, b"record-1", &[&group_id.id], &mpk)
+                    .expect("Sealing failed");
+
+            let shares: Vec<DecryptionShare> = [1, 2]
+                .iter()
+                .map(|i| {
+                    encryption::DIBTDEncryption::share_decrypt_labeled(
+                        &keys[0],
+                        b"record-1",
+                        &private_shares[i],
+                    )
+                    .expect("Share decryption failed")
+                })
+                .collect();
+
+            // The packet records its algorithm
+            assert_eq!(packet.algorithm, algorithm);
+            let opened = EnvelopeEncryption::open(&keys[0], &packet, b"record-1", &shares, 2)
+                .expect("Opening failed");
+            assert_eq!(opened, plaintext);
+
+            // Opening under another algorithm fails
+            let mut relabeled = packet.clone();
+            relabeled.algorithm = if algorithm == aead::AeadAlgorithm::Aes256Gcm {
+                aead::AeadAlgorithm::Aes256GcmSiv
+            } else {
+                aead::AeadAlgorithm::Aes256Gcm
+            };
+            assert!(
+                EnvelopeEncryption::open(&keys[0], &relabeled, b"record-1", &shares, 2).is_err()
+            );
+
+            // Streams record the algorithm in their header
+            let mut sealed = Vec::new();
+            let study = vec![0x5Au8; 150_000];
+            let stream_key = EnvelopeEncryption::seal_stream(
+                &study[..],
+                &mut sealed,
+                b"record-1",
+                &group_id.id,
+                &mpk,
+            )
+            .expect("Stream sealing failed");
+            assert_eq!(sealed[1], algorithm.id());
+            let stream_shares: Vec<DecryptionShare> = [1, 2]
+                .iter()
+                .map(|i| {
+                    encryption::DIBTDEncryption::share_decrypt_labeled(
+                        &stream_key,
+                        b"record-1",
+                        &private_shares[i],
+                    )
+                    .expect("Share decryption failed")
+                })
+                .collect();
+            let mut opened = Vec::new();
+            EnvelopeEncryption::open_stream(
+                &stream_key,
+                &sealed[..],
+                &mut opened,
+                b"record-1",
+                &stream_shares,
+                2,
+            )
+            .expect("Stream opening failed");
+            assert_eq!(opened, study);
+        }
+
+        // Parameters packed before the algorithm id existed still unpack as AES-256-GCM
+        let key = aead::AEADCipher::generate_key();
+        let nonce = aead::AEADCipher::generate_nonce();
+        let mut legacy = key.to_vec();
+        legacy.extend_from_slice(&nonce);
+        legacy.extend_from_slice(&[7u8; 16]);
+        let params = aead::AeadParams::unpack(&legacy).expect("Legacy unpacking failed");
+        assert_eq!(params.algorithm, aead::AeadAlgorithm::Aes256Gcm);
+        assert!(!params.key_committing);
+        assert_eq!(
+            (params.key, params.nonce, params.tag),
+            (key, nonce.to_vec(), [7u8; 16])
+        );
+
+        // Unknown ids and nonces of the wrong length are rejected
+        let mut unknown = legacy.clone();
+        unknown.insert(32, 9);
+        assert!(aead::AeadParams::unpack(&unknown).is_err());
+
+        // The key leads the packed form, inside the 32 bytes masked by H2
+        let packed = aead::AEADCipher::pack_aead_params(&key, &nonce, &[7u8; 16]);
+        assert_eq!(&packed[..32], &key);
+        assert_eq!(packed[32], aead::AeadAlgorithm::Aes256Gcm.id());
+        assert_eq!(
+            aead::AEADCipher::unpack_aead_params(&packed).unwrap(),
+            (key, nonce, [7u8; 16])
+        );
+        assert_eq!(
+            aead::AEADCipher::unpack_aead_params(&legacy).unwrap(),
+            (key, nonce, [7u8; 16])
+        );
+        let xchacha = aead::AeadParams {
+            algorithm: aead::AeadAlgorithm::XChaCha20Poly1305,
+            key_committing: false,
+            key,
+            nonce: nonce.to_vec(),
+            tag: [7u8; 16],
+        };
+        assert!(xchacha.pack().is_err());
+
+        // The AES-256-GCM wrapper refuses parameters it cannot represent
+        let xchacha = aead::AeadParams {
+            nonce: vec![0u8; 24],
+            ..xchacha
+        };
+        assert!(aead::AEADCipher::unpack_aead_params(&xchacha.pack().unwrap()).is_err());
+    }
+
+    /// GF(2^128) multiplication in GCM's bit order
+    fn gcm_mul(x: u128, y: u128) -> u128 {
+        let mut z = 0u128;
+        let mut v = y;
+        for i in 0..128 {
+            if (x >> (127 - i)) & 1 == 1 {
+                z ^= v;
+            }
+            v = if v & 1 == 1 {
+                (v >> 1) ^ (0xE1 << 120)
+            } else {
+                v >> 1
+            };
+        }
+        z
+    }
+
+    /// Inverse as x^(2^128 - 2) = product of x^(2^i) for i in 1..128
+    fn gcm_inverse(x: u128) -> u128 {
+        let mut acc = 1u128 << 127;
+        let mut power = x;
+        for _ in 1..128 {
+            power = gcm_mul(power, power);
+            acc = gcm_mul(acc, power);
+        }
+        acc
+    }
+
+    fn aes_block(key: &[u8; 32], block: u128) -> u128 {
+        use aes_gcm::aes::cipher::{BlockEncrypt, KeyInit};
+        let cipher = aes_gcm::aes::Aes256::new(key.into());
+        let mut bytes = block.to_be_bytes().into();
+        cipher.encrypt_block(&mut bytes);
+        u128::from_be_bytes(bytes.into())
+    }
+
+    /// Build a one-block AES-GCM ciphertext ‖ tag that authenticates under both keys
+    ///
+    /// With an 8-byte AAD block A and length block L the tag is
+    /// E_K(J0) ⊕ A·H³ ⊕ C·H² ⊕ L·H, which is linear in C, so C can be
+    /// solved for so that both keys yield the same tag.
+    fn gcm_key_collision(k1: &[u8; 32], k2: &[u8; 32], nonce: &[u8; 12], aad: &[u8; 8]) -> Vec<u8> {
+        let mut j0 = [0u8; 16];
+        j0[..12].copy_from_slice(nonce);
+        j0[15] = 1;
+        let j0 = u128::from_be_bytes(j0);
+
+        let mut aad_block = [0u8; 16];
+        aad_block[..8].copy_from_slice(aad);
+        let aad_block = u128::from_be_bytes(aad_block);
+        let lengths = (64u128 << 64) | 128;
+
+        // Tag contribution of everything except the ciphertext block
+        let partial = |key: &[u8; 32]| {
+            let h = aes_block(key, 0);
+            let h2 = gcm_mul(h, h);
+            (
+                h2,
+                aes_block(key, j0) ^ gcm_mul(aad_block, gcm_mul(h2, h)) ^ gcm_mul(lengths, h),
+            )
+        };
+        let (h1_sq, t1) = partial(k1);
+        let (h2_sq, t2) = partial(k2);
+
+        let block = gcm_mul(t1 ^ t2, gcm_inverse(h1_sq ^ h2_sq));
+        let tag = t1 ^ gcm_mul(block, h1_sq);
+        [block.to_be_bytes(), tag.to_be_bytes()].concat()
+    }
+
+    #[test]
+    fn test_key_committing_envelopes() {
+        let (mpk, master_shares) = setup_test_system(3, 2).expect("DKG setup failed");
+        let groups: Vec<GroupIdentity> = ["cardiology", "billing"]
+            .iter()
+            .map(|id| GroupIdentity {
+                id: id.to_string(),
+                threshold: 2,
+                members: 3,
+            })
+            .collect();
+        let ad = b"record-1";
+        let nonce = [0x24u8; 12];
+        let k1 = [0x11u8; 32];
+        let k2 = [0x22u8; 32];
+        let forged = gcm_key_collision(&k1, &k2, &nonce, ad);
+        let mut tag = [0u8; 16];
+        tag.copy_from_slice(&forged[16..]);
+
+        // Plain AES-GCM opens the forged payload under both keys, to different plaintexts
+        let gcm = aead::AeadAlgorithm::Aes256Gcm;
+        let under_k1 = aead::AEADCipher::decrypt_with(gcm, &k1, &nonce, &forged, ad)
+            .expect("Forgery should open under the first key");
+        let under_k2 = aead::AEADCipher::decrypt_with(gcm, &k2, &nonce, &forged, ad)
+            .expect("Forgery should open under the second key");
+        assert_ne!(under_k1, under_k2);
+
+        // Hand each group its own key for the same payload
+        let encapsulate_for = |group: &GroupIdentity, packed: &[u8]| {
+            let private_shares = dkg::distributed_keygen(&master_shares, group, 2)
+                .expect("Distributed keygen failed");
+            let key = encryption::DIBTDEncryption::encrypt_labeled(packed, ad, &group.id, &mpk)
+                .expect("Encapsulation failed");
+            let shares: Vec<DecryptionShare> = [1, 2]
+                .iter()
+                .map(|i| {
+                    encryption::DIBTDEncryption::share_decrypt_labeled(&key, ad, &private_shares[i])
+                        .expect("Share decryption failed")
+                })
+                .collect();
+            (key, shares)
+        };
+        let params = |key: &[u8; 32], key_committing: bool| {
+            aead::AeadParams {
+                algorithm: gcm,
+                key_committing,
+                key: *key,
+                nonce: nonce.to_vec(),
+                tag,
+            }
+            .pack()
+            .unwrap()
+        };
+
+        // `open` accepts only encapsulated seeds, so neither group opens the forgery,
+        // whatever the packed parameters claim about commitment
+        let commitment = aead::AEADCipher::encrypt_committing(gcm, &k1, &nonce, b"", ad).unwrap();
+        let mut committed = commitment[..aead::COMMITMENT_LEN].to_vec();
+        committed.extend_from_slice(&forged);
+        let forged_packet = AEADPacket {
+            version: AEAD_PACKET_VERSION,
+            algorithm: gcm,
+            nonce: nonce.to_vec(),
+            commitment: commitment[..aead::COMMITMENT_LEN].try_into().unwrap(),
+            ciphertext: forged[..16].to_vec(),
+            tag,
+            associated_data: ad.to_vec(),
+        };
+        for (group, key) in [(&groups[0], &k1), (&groups[1], &k2)] {
+            for key_committing in [false, true] {
+                let (encapsulated, shares) = encapsulate_for(group, &params(key, key_committing));
+                assert!(matches!(
+                    EnvelopeEncryption::open(&encapsulated, &forged_packet, ad, &shares, 2),
+                    Err(DIBTDError::InvalidCiphertext)
+                ));
+            }
+        }
+
+        // The legacy API trusts the packed flag: without commitment both groups
+        // accept and see different records
+        let open_legacy = |group: &GroupIdentity, packed: &[u8], payload: &[u8]| {
+            let (encapsulated, shares) = encapsulate_for(group, packed);
+            EnvelopeEncryption::open_legacy(&encapsulated, payload, ad, &shares, 2)
+        };
+        let a = open_legacy(&groups[0], &params(&k1, false), &forged).unwrap();
+        let b = open_legacy(&groups[1], &params(&k2, false), &forged).unwrap();
+        assert_ne!(a, b);
+
+        // With commitment the payload binds to one key, so the second group rejects it
+        assert_eq!(
+            open_legacy(&groups[0], &params(&k1, true), &committed).unwrap(),
+            under_k1
+        );
+        assert!(open_legacy(&groups[1], &params(&k2, true), &committed).is_err());
+
+        // Honest envelopes are committing by default and open for every group
+        let (keys, packet) = EnvelopeEncryption::seal_for_groups(
+            b"Heart Rate: 72 bpm",
+            ad,
+            &["cardiology", "billing"],
+            &mpk,
+        )
+        .expect("Sealing failed");
+        assert_eq!(packet.ciphertext.len(), 18);
+        for (group, key) in groups.iter().zip(&keys) {
+            let private_shares = dkg::distributed_keygen(&master_shares, group, 2)
+                .expect("Distributed keygen failed");
+            let shares: Vec<DecryptionShare> = [1, 2]
+                .iter()
+                .map(|i| {
+                    encryption::DIBTDEncryption::share_decrypt_labeled(key, ad, &private_shares[i])
+                        .expect("Share decryption failed")
+                })
+                .collect();
+            assert_eq!(
+                EnvelopeEncryption::open(key, &packet, ad, &shares, 2).unwrap(),
+                b"Heart Rate: 72 bpm"
+            );
+        }
+    }
+
+    #[test]
+    fn test_seed_encapsulated_envelopes() {
+        let (mpk, master_shares) = setup_test_system(3, 2).expect("DKG setup failed");
+        let group_id = GroupIdentity {
+            id: "test_group".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let private_shares = dkg::distributed_keygen(&master_shares, &group_id, 2)
+            .expect("Distributed keygen failed");
+        let shares_for = |key: &Ciphertext| -> Vec<DecryptionShare> {
+            [1, 2]
+                .iter()
+                .map(|i| {
+                    encryption::DIBTDEncryption::share_decrypt_labeled(
+                        key,
+                        b"record-1",
+                        &private_shares[i],
+                    )
+                    .expect("Share decryption failed")
+                })
+                .collect()
+        };
+
+        // Key and nonce derivation is deterministic and separated by algorithm
+        let seed = [0x42u8; aead::SEED_LEN];
+        let gcm = aead::AeadAlgorithm::Aes256Gcm;
+        let xchacha = aead::AeadAlgorithm::XChaCha20Poly1305;
+        let (key, nonce) = aead::AEADCipher::derive_from_seed(&seed, gcm).unwrap();
+        assert_eq!(
+            aead::AEADCipher::derive_from_seed(&seed, gcm).unwrap(),
+            (key, nonce.clone())
+        );
+        assert_eq!(nonce.len(), 12);
+        let (xkey, xnonce) = aead::AEADCipher::derive_from_seed(&seed, xchacha).unwrap();
+        assert_ne!(xkey, key);
+        assert_eq!(xnonce.len(), 24);
+
+        // Only the 32-byte seed is encapsulated, so all of it is masked by H2
+        let plaintext = b"Heart Rate: 72 bpm";
+        let (key, packet) =
+            EnvelopeEncryption::seal(plaintext, b"record-1", &group_id.id, &mpk).unwrap();
+        assert_eq!(key.f.len(), aead::SEED_LEN);
+        let shares = shares_for(&key);
+        let secret =
+            encryption::DIBTDEncryption::decrypt_labeled(&key, b"record-1", &shares, 2).unwrap();
+        assert_eq!(secret.len(), aead::SEED_LEN);
+        assert_eq!(
+            EnvelopeEncryption::open(&key, &packet, b"record-1", &shares, 2).unwrap(),
+            plaintext
+        );
+        assert!(EnvelopeEncryption::open(&key, &packet, b"record-2", &shares, 2).is_err());
+
+        // Envelopes with packed parameters from before seed encapsulation open
+        // only through the legacy API
+        let legacy_key = aead::AEADCipher::generate_key();
+        let legacy_nonce = aead::AEADCipher::generate_nonce();
+        let legacy_payload =
+            aead::AEADCipher::encrypt(&legacy_key, &legacy_nonce, plaintext, b"record-1").unwrap();
+        let mut tag = [0u8; 16];
+        tag.copy_from_slice(&legacy_payload[legacy_payload.len() - 16..]);
+        let packed = aead::AEADCipher::pack_aead_params(&legacy_key, &legacy_nonce, &tag);
+        let legacy =
+            encryption::DIBTDEncryption::encrypt_labeled(&packed, b"record-1", &group_id.id, &mpk)
+                .unwrap();
+        let legacy_shares = shares_for(&legacy);
+        assert!(matches!(
+            EnvelopeEncryption::open(&legacy, &packet, b"record-1", &legacy_shares, 2),
+            Err(DIBTDError::InvalidCiphertext)
+        ));
+        assert_eq!(
+            EnvelopeEncryption::open_legacy(
+                &legacy,
+                &legacy_payload,
+                b"record-1",
+                &legacy_shares,
+                2
+            )
+            .unwrap(),
+            plaintext
+        );
+
+        // So do streams sealed that way: prefix in the nonce slot, final tag in the tag slot
+        let study = vec![0x5Au8; 100_000];
+        let mut sealed = Vec::new();
+        let mut encryptor =
+            stream::StreamEncryptor::new(&legacy_key, b"record-1", &mut sealed).unwrap();
+        std::io::Write::write_all(&mut encryptor, &study).unwrap();
+        let mut nonce = [0u8; 12];
+        nonce[..7].copy_from_slice(encryptor.nonce_prefix());
+        encryptor.finish().unwrap();
+        tag.copy_from_slice(&sealed[sealed.len() - 16..]);
+        let packed = aead::AEADCipher::pack_aead_params(&legacy_key, &nonce, &tag);
+        let legacy =
+            encryption::DIBTDEncryption::encrypt_labeled(&packed, b"record-1", &group_id.id, &mpk)
+                .unwrap();
+        let legacy_shares = shares_for(&legacy);
+        let mut opened = Vec::new();
+        assert!(matches!(
+            EnvelopeEncryption::open_stream(
+                &legacy,
+                &sealed[..],
+                &mut opened,
+                b"record-1",
+                &legacy_shares,
+                2,
+            ),
+            Err(DIBTDError::InvalidCiphertext)
+        ));
+        assert!(opened.is_empty());
+        EnvelopeEncryption::open_stream_legacy(
+            &legacy,
+            &sealed[..],
+            &mut opened,
+            b"record-1",
+            &legacy_shares,
+            2,
+        )
+        .unwrap();
+        assert_eq!(opened, study);
+    }
+
+    #[test]
+    fn test_aead_packets_and_envelopes() {
+        let (mpk, master_shares) = setup_test_system(3, 2).expect("DKG setup failed");
+        let group_id = GroupIdentity {
+            id: "test_group".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let private_shares = dkg::distributed_keygen(&master_shares, &group_id, 2)
+            .expect("Distributed keygen failed");
+        let plaintext = b"Patient: John Doe\nHeart Rate: 72 bpm";
+
+        // Packets carry version, algorithm, nonce, commitment and tag separately
+        let key = aead::AEADCipher::generate_key();
+        let algorithm = aead::AeadAlgorithm::XChaCha20Poly1305;
+        let nonce = aead::AEADCipher::generate_nonce_for(algorithm);
+        let packet =
+            aead::AEADCipher::encrypt_packet(algorithm, &key, &nonce, plaintext, b"record-1")
+                .expect("Packet encryption failed");
+        assert_eq!(packet.version, AEAD_PACKET_VERSION);
+        assert_eq!(packet.algorithm, algorithm);
+        assert_eq!(packet.nonce.len(), 24);
+        assert_eq!(packet.ciphertext.len(), plaintext.len());
+        assert_eq!(packet.associated_data, b"record-1");
+
+        let json = serde_json::to_string(&packet).unwrap();
+        let restored: AEADPacket = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, packet);
+        assert_eq!(
+            aead::AEADCipher::decrypt_packet(&key, &restored).unwrap(),
+            plaintext
+        );
+
+        // Unknown versions, wrong keys and altered fields are rejected
+        let mut future = packet.clone();
+        future.version += 1;
+        assert!(aead::AEADCipher::decrypt_packet(&key, &future).is_err());
+        assert!(
+            aead::AEADCipher::decrypt_packet(&aead::AEADCipher::generate_key(), &packet).is_err()
+        );
+        let mut relabeled = packet.clone();
+        relabeled.associated_data = b"record-2".to_vec();
+        assert!(aead::AEADCipher::decrypt_packet(&key, &relabeled).is_err());
+        let mut recommitted = packet.clone();
+        recommitted.commitment[0] ^= 1;
+        assert!(aead::AEADCipher::decrypt_packet(&key, &recommitted).is_err());
+
+        // An envelope pairs the packet with the encapsulated seed
+        let envelope =
+            EnvelopeEncryption::seal_envelope(plaintext, b"record-1", &group_id.id, &mpk)
+                .expect("Sealing failed");
+        assert_eq!(envelope.packet.algorithm, aead::AeadAlgorithm::default());
+        let shares: Vec<DecryptionShare> = [1, 3]
+            .iter()
+            .map(|i| {
+                encryption::DIBTDEncryption::share_decrypt_labeled(
+                    &envelope.encapsulated_key,
+                    &envelope.packet.associated_data,
+                    &private_shares[i],
+                )
+                .expect("Share decryption failed")
+            })
+            .collect();
+        let json = serde_json::to_vec(&envelope).unwrap();
+        let restored: Envelope = serde_json::from_slice(&json).unwrap();
+        assert_eq!(
+            EnvelopeEncryption::open_envelope(&restored, &shares, 2).unwrap(),
+            plaintext
+        );
+        assert!(matches!(
+            EnvelopeEncryption::open_envelope(&envelope, &shares[..1], 2),
+            Err(DIBTDError::InsufficientShares(1, 2))
+        ));
+
+        // A packet from another envelope does not open under this seed
+        let other = EnvelopeEncryption::seal_envelope(plaintext, b"record-1", &group_id.id, &mpk)
+            .expect("Sealing failed");
+        let swapped = Envelope {
+            encapsulated_key: envelope.encapsulated_key.clone(),
+            packet: other.packet,
+        };
+        assert!(EnvelopeEncryption::open_envelope(&swapped, &shares, 2).is_err());
+    }
+
+    #[test]
+    fn test_hash_to_scalar_domain_separation() {
+        // RFC 9380, appendix K.1
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        assert_eq!(
+            utils::to_hex(&hash::expand_message_xmd(b"", dst, 0x20).unwrap()),
+            "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"
+        );
+        assert_eq!(
+            utils::to_hex(&hash::expand_message_xmd(b"abc", dst, 0x20).unwrap()),
+            "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"
+        );
+        assert!(hash::expand_message_xmd(b"abc", dst, 255 * 32 + 1).is_err());
+
+        let suite = Ciphersuite::default();
+        let tag = hash::Domain::H1.dst(&suite);
+        assert!(tag.starts_with(hash::ciphersuite_id(&suite).as_bytes()));
+        assert!(hash::ciphersuite_id(&suite).contains(group::Curve::ID.name()));
+        assert!(Ciphersuite::default()
+            .to_string()
+            .starts_with(group::Curve::ID.name()));
+
+        // Same input, different uses
+        let input = b"group-cardiology";
+        assert_eq!(
+            utils::hash_h1(input, &suite),
+            hash::hash_to_scalar(input, &tag)
+        );
+        assert_eq!(
+            utils::hash_h2_bytes(input, &suite),
+            hash::hash_to_bytes(input, &hash::Domain::H2.dst(&suite))
+        );
+        assert_ne!(
+            utils::hash_h2_bytes(input, &suite),
+            hash::hash_to_bytes(input, &hash::Domain::H1.dst(&suite))
+        );
+        assert_ne!(
+            hash::hash_to_scalar(input, &hash::Domain::H1.dst(&suite)),
+            hash::hash_to_scalar(input, &hash::Domain::Schnorr.dst(&suite))
+        );
+
+        // The Schnorr challenge binds the prover's public key
+        let secret = utils::scalar_from_u32(11);
+        let public_key = group::Curve::mul_generator(&secret).unwrap();
+        let other_key = group::Curve::mul_generator(&utils::scalar_from_u32(12)).unwrap();
+        let proof = utils::schnorr_prove(&secret, "ctx", &suite).expect("Proof generation failed");
+        assert_ne!(
+            utils::schnorr_challenge(&proof.r, &public_key, "ctx", &suite),
+            utils::schnorr_challenge(&proof.r, &other_key, "ctx", &suite)
+        );
+        assert!(utils::schnorr_verify(&proof, &public_key, "ctx", &suite).unwrap());
+        assert!(!utils::schnorr_verify(&proof, &other_key, "ctx", &suite).unwrap());
+    }
+
+    #[test]
+    fn test_ciphersuites() {
+        use ciphersuite::{CurveId, HashId};
+
+        let default = Ciphersuite::default();
+        assert_eq!(default.curve, group::Curve::ID);
+        let parsed: Ciphersuite = "secp256k1-SHA256-AESGCM".parse().unwrap();
+        assert_eq!(parsed.curve, CurveId::Secp256k1);
+        assert_eq!(parsed.hash, HashId::Sha256);
+        assert_eq!(parsed.aead, aead::AeadAlgorithm::Aes256Gcm);
+        assert_eq!(parsed.to_string(), "secp256k1-SHA256-AESGCM");
+        for unknown in [
+            "secp256k1-SHA256",
+            "secp256k1-SHA3-AESGCM",
+            "ed448-SHA256-AESGCM",
+        ] {
+            let err = unknown.parse::<Ciphersuite>().unwrap_err();
+            assert_eq!(err.code(), "unsupported_ciphersuite");
+        }
+
+        // Only this build's curve can run a DKG
+        let foreign = Ciphersuite {
+            curve: if default.curve == CurveId::P256 {
+                CurveId::Secp256k1
+            } else {
+                CurveId::P256
+            },
+            ..default
+        };
+        assert!(!foreign.is_supported());
+        assert!(matches!(
+            dkg::DKGProtocol::with_ciphersuite(3, 2, foreign),
+            Err(DIBTDError::UnsupportedCiphersuite(_))
+        ));
+
+        // The suite travels from the DKG into keys, ciphertexts and their JSON
+        let suite = Ciphersuite::with_aead(aead::AeadAlgorithm::ChaCha20Poly1305);
+        let (mpk, master_shares) = setup_test_system_with_suite(3, 2, suite).unwrap();
+        assert_eq!(mpk.params.ciphersuite, suite);
+        let group = GroupIdentity {
+            id: "suite-group".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let private_shares = dkg::distributed_keygen(&master_shares, &group, 2).unwrap();
+        assert!(private_shares
+            .values()
+            .all(|share| share.ciphersuite == suite));
+
+        let ciphertext =
+            encryption::DIBTDEncryption::encrypt(b"lab result", &group.id, &mpk).unwrap();
+        assert_eq!(ciphertext.ciphersuite, suite);
+        let json = serde_json::to_value(&ciphertext).unwrap();
+        assert_eq!(json["ciphersuite"], suite.to_string());
+        let mpk_json = serde_json::to_value(&mpk).unwrap();
+        assert_eq!(mpk_json["params"]["ciphersuite"], suite.to_string());
+
+        // Ciphertexts and parameters without a suite are refused, not defaulted
+        let mut unlabeled = json.clone();
+        unlabeled.as_object_mut().unwrap().remove("ciphersuite");
+        assert!(serde_json::from_value::<Ciphertext>(unlabeled).is_err());
+        let mut unlabeled = mpk_json.clone();
+        unlabeled["params"]
+            .as_object_mut()
+            .unwrap()
+            .remove("ciphersuite");
+        assert!(serde_json::from_value::<MasterPublicKey>(unlabeled).is_err());
+
+        // Envelopes use the suite's AEAD algorithm
+        let envelope =
+            EnvelopeEncryption::seal_envelope(b"note", b"record-9", &group.id, &mpk).unwrap();
+        assert_eq!(
+            envelope.packet.algorithm,
+            aead::AeadAlgorithm::ChaCha20Poly1305
+        );
+        let shares: Vec<DecryptionShare> = [1, 2]
+            .iter()
+            .map(|i| {
+                encryption::DIBTDEncryption::share_decrypt_labeled(
+                    &envelope.encapsulated_key,
+                    b"record-9",
+                    &private_shares[i],
+                )
+                .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            EnvelopeEncryption::open_envelope(&envelope, &shares, 2).unwrap(),
+            b"note"
+        );
+
+        // Mismatched suites are refused
+        let mut relabeled = envelope.clone();
+        relabeled.encapsulated_key.ciphersuite.aead = aead::AeadAlgorithm::Aes256Gcm;
+        let err = EnvelopeEncryption::open_envelope(&relabeled, &shares, 2).unwrap_err();
+        assert_eq!(err.code(), "ciphersuite_mismatch");
+
+        // The suite is hashed into the well-formedness proof, so it cannot be
+        // rewritten after encryption
+        let mut rewritten = ciphertext.clone();
+        rewritten.ciphersuite.aead = aead::AeadAlgorithm::Aes256GcmSiv;
+        assert!(matches!(
+            rewritten.verify(),
+            Err(DIBTDError::InvalidCiphertext)
+        ));
+
+        let mut moved = ciphertext.clone();
+        moved.ciphersuite = foreign;
+        assert!(matches!(
+            encryption::DIBTDEncryption::share_decrypt(&moved, &private_shares[&1]),
+            Err(DIBTDError::CiphersuiteMismatch { .. })
+        ));
+        assert!(matches!(
+            encryption::DIBTDEncryption::decrypt(&moved, &shares, 2),
+            Err(DIBTDError::UnsupportedCiphersuite(_))
+        ));
+
+        // A suite differing only in its AEAD algorithm is refused as well, by
+        // key shares and by the combiner checking the decryption shares
+        assert!(matches!(
+            encryption::DIBTDEncryption::share_decrypt(&rewritten, &private_shares[&1]),
+            Err(DIBTDError::CiphersuiteMismatch { .. })
+        ));
+        assert!(matches!(
+            encryption::DIBTDEncryption::decrypt(&rewritten, &shares, 2),
+            Err(DIBTDError::CiphersuiteMismatch { .. })
+        ));
+        let json = serde_json::to_value(&shares[0]).unwrap();
+        assert_eq!(json["ciphersuite"], suite.to_string());
+    }
+
+    #[test]
+    fn test_reencryption_unlinkable_copies() {
+        let (mpk, master_shares) = setup_test_system(3, 2).unwrap();
+        let group = GroupIdentity {
+            id: "backup-group".to_string(),
+            threshold: 2,
+            members: 3,
+        };
+        let private_shares = dkg::distributed_keygen(&master_shares, &group, 2).unwrap();
+        let shares_for = |ciphertext: &Ciphertext, label: &[u8]| -> Vec<DecryptionShare> {
+            [1, 2]
+                .iter()
+                .map(|i| {
+                    encryption::DIBTDEncryption::share_decrypt_labeled(
+                        ciphertext,
+                        label,
+                        &private_shares[i],
+                    )
+                    .unwrap()
+                })
+                .collect()
+        };
+
+        // A naively re-randomized D no longer satisfies the proof
+        let original =
+            encryption::DIBTDEncryption::encrypt_labeled(b"data key", b"rec-1", &group.id, &mpk)
+                .unwrap();
+        let blind = group::Curve::scalar_from_u64(7);
+        let mut shifted = original.clone();
+        shifted.d =
+            group::Curve::add(&original.d, &group::Curve::mul_generator(&blind).unwrap()).unwrap();
+        shifted.delta = group::Curve::scalar_add(&original.delta, &blind);
+        assert!(shifted.verify_labeled(b"rec-1").is_err());
+
+        // A quorum re-encrypts instead
+        let shares = shares_for(&original, b"rec-1");
+        let copy = encryption::DIBTDEncryption::reencrypt_labeled(
+            &original, b"rec-1", &shares, 2, &group.id, &mpk,
+        )
+        .unwrap();
+        assert_ne!(copy.d, original.d);
+        assert_ne!(copy.e, original.e);
+        assert_ne!(copy.f, original.f);
+        copy.verify_labeled(b"rec-1").unwrap();
+        assert!(copy.verify().is_err());
+        let copy_shares = shares_for(&copy, b"rec-1");
+        assert_eq!(
+            encryption::DIBTDEncryption::decrypt_labeled(&copy, b"rec-1", &copy_shares, 2).unwrap(),
+            b"data key"
+        );
+        assert!(encryption::DIBTDEncryption::reencrypt(
+            &original,
+            &shares[..1],
+            2,
+            &group.id,
+            &mpk
+        )
+        .is_err());
+
+        // Envelopes get a fresh payload as well as a fresh key
+        let envelope =
+            EnvelopeEncryption::seal_envelope(b"discharge summary", b"rec-2", &group.id, &mpk)
+                .unwrap();
+        let shares = shares_for(&envelope.encapsulated_key, b"rec-2");
+        let backup =
+            EnvelopeEncryption::reseal_envelope(&envelope, &shares, 2, &group.id, &mpk).unwrap();
+        assert_ne!(backup.packet.ciphertext, envelope.packet.ciphertext);
+        assert_ne!(backup.packet.nonce, envelope.packet.nonce);
+        assert_ne!(backup.encapsulated_key.d, envelope.encapsulated_key.d);
+        assert_eq!(
+            backup.packet.associated_data,
+            envelope.packet.associated_data
+        );
+        assert_eq!(backup.packet.algorithm, envelope.packet.algorithm);
+        let backup_shares = shares_for(&backup.encapsulated_key, b"rec-2");
+        assert_eq!(
+            EnvelopeEncryption::open_envelope(&backup, &backup_shares, 2).unwrap(),
+            b"discharge summary"
+        );
+    }
 }